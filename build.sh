@@ -24,6 +24,27 @@ cargo build --release --bin key-listener-simulation --features simulation --no-d
 echo "Building mouse-only binary..."
 cargo build --release --bin key-listener-mouse --features mouse --no-default-features
 
+echo "Building combined-only binary..."
+cargo build --release --bin key-listener-combined --features combined --no-default-features
+
+echo "Building duplex-only binary..."
+cargo build --release --bin key-listener-duplex --features duplex --no-default-features
+
+echo "Building hotkey-only binary..."
+cargo build --release --bin key-listener-hotkey --features hotkey --no-default-features
+
+echo "Building sequence-only binary..."
+cargo build --release --bin key-listener-sequence --features sequence --no-default-features
+
+echo "Building gesture-only binary..."
+cargo build --release --bin key-listener-gesture --features gesture --no-default-features
+
+echo "Building text-only binary..."
+cargo build --release --bin key-listener-text --features text --no-default-features
+
+echo "Building stats-only binary..."
+cargo build --release --bin key-listener-stats --features stats --no-default-features
+
 echo "All builds completed!"
 echo ""
 echo "Available binaries:"
@@ -33,3 +54,10 @@ echo "- key-listener-complex (complex mode only)"
 echo "- key-listener-hold-release (hold and release mode only)"
 echo "- key-listener-simulation (simulation mode only)"
 echo "- key-listener-mouse (mouse mode only)"
+echo "- key-listener-combined (combined mode only)"
+echo "- key-listener-duplex (duplex mode only)"
+echo "- key-listener-hotkey (hotkey mode only)"
+echo "- key-listener-sequence (sequence mode only)"
+echo "- key-listener-gesture (gesture mode only)"
+echo "- key-listener-text (text mode only)"
+echo "- key-listener-stats (stats mode only)"
@@ -57,6 +57,13 @@ if [ $# -eq 1 ]; then
     build_target "$TARGET" "hold_and_release" "key-listener-hold-release"
     build_target "$TARGET" "simulation" "key-listener-simulation"
     build_target "$TARGET" "mouse" "key-listener-mouse"
+    build_target "$TARGET" "combined" "key-listener-combined"
+    build_target "$TARGET" "duplex" "key-listener-duplex"
+    build_target "$TARGET" "hotkey" "key-listener-hotkey"
+    build_target "$TARGET" "sequence" "key-listener-sequence"
+    build_target "$TARGET" "gesture" "key-listener-gesture"
+    build_target "$TARGET" "text" "key-listener-text"
+    build_target "$TARGET" "stats" "key-listener-stats"
     
     echo -e "${GREEN}Cross-compilation completed for $TARGET${NC}"
     echo "Binaries are located in: target/$TARGET/release/"
@@ -78,6 +85,13 @@ for target in "${TARGETS[@]}"; do
         build_target "$target" "hold_and_release" "key-listener-hold-release"
         build_target "$target" "simulation" "key-listener-simulation"
         build_target "$target" "mouse" "key-listener-mouse"
+        build_target "$target" "combined" "key-listener-combined"
+        build_target "$target" "duplex" "key-listener-duplex"
+        build_target "$target" "hotkey" "key-listener-hotkey"
+        build_target "$target" "sequence" "key-listener-sequence"
+        build_target "$target" "gesture" "key-listener-gesture"
+        build_target "$target" "text" "key-listener-text"
+        build_target "$target" "stats" "key-listener-stats"
     else
         echo -e "${RED}✗ Failed to install target $target${NC}"
     fi
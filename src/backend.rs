@@ -0,0 +1,153 @@
+use enigo::{Axis, Button, Coordinate, Direction, Enigo, InputResult, Key, Keyboard, Mouse, NewConError, Settings};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The input operations the simulation mode needs, plus the clock it paces
+/// animations and delays with.
+pub trait InputBackend {
+    fn key(&mut self, key: Key, direction: Direction) -> InputResult<()>;
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()>;
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()>;
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()>;
+    fn location(&self) -> InputResult<(i32, i32)>;
+    fn text(&mut self, text: &str) -> InputResult<()>;
+    /// Time elapsed since the backend was created.
+    fn now(&self) -> Duration;
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// Injects real input through Enigo.
+pub struct EnigoBackend {
+    enigo: Enigo,
+    started: Instant,
+}
+
+impl EnigoBackend {
+    pub fn new() -> Result<Self, NewConError> {
+        Ok(EnigoBackend {
+            enigo: Enigo::new(&Settings::default())?,
+            started: Instant::now(),
+        })
+    }
+}
+
+impl InputBackend for EnigoBackend {
+    fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
+        self.enigo.key(key, direction)
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.enigo.button(button, direction)
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        self.enigo.move_mouse(x, y, coordinate)
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        self.enigo.scroll(length, axis)
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        self.enigo.location()
+    }
+
+    fn text(&mut self, text: &str) -> InputResult<()> {
+        self.enigo.text(text)
+    }
+
+    fn now(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Key { key: Key, direction: Direction },
+    Button { button: Button, direction: Direction },
+    MoveMouse { x: i32, y: i32, coordinate: Coordinate },
+    Scroll { length: i32, axis: Axis },
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedAction {
+    pub at: Duration,
+    pub action: Action,
+}
+
+/// A mock backend that logs every action against a virtual clock.
+///
+/// `sleep` advances the clock instantly, so animated moves and delays run
+/// without waiting and show up as the timestamps of the recorded actions.
+#[derive(Debug, Default, Clone)]
+pub struct RecordingBackend {
+    clock: Duration,
+    cursor: (i32, i32),
+    actions: Vec<RecordedAction>,
+}
+
+impl RecordingBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_cursor(x: i32, y: i32) -> Self {
+        RecordingBackend { cursor: (x, y), ..Self::default() }
+    }
+
+    pub fn actions(&self) -> &[RecordedAction] {
+        &self.actions
+    }
+
+    fn record(&mut self, action: Action) {
+        self.actions.push(RecordedAction { at: self.clock, action });
+    }
+}
+
+impl InputBackend for RecordingBackend {
+    fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
+        self.record(Action::Key { key, direction });
+        Ok(())
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.record(Action::Button { button, direction });
+        Ok(())
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        self.cursor = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => (self.cursor.0 + x, self.cursor.1 + y),
+        };
+        self.record(Action::MoveMouse { x, y, coordinate });
+        Ok(())
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        self.record(Action::Scroll { length, axis });
+        Ok(())
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Ok(self.cursor)
+    }
+
+    fn text(&mut self, text: &str) -> InputResult<()> {
+        self.record(Action::Text(text.to_string()));
+        Ok(())
+    }
+
+    fn now(&self) -> Duration {
+        self.clock
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.clock += duration;
+    }
+}
@@ -0,0 +1,5 @@
+use key_listener::combined::combined_handling;
+
+fn main() {
+    combined_handling();
+}
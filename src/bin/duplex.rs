@@ -0,0 +1,5 @@
+use key_listener::duplex::duplex_handling;
+
+fn main() {
+    duplex_handling();
+}
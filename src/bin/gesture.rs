@@ -0,0 +1,5 @@
+use key_listener::gesture::gesture_handling;
+
+fn main() {
+    gesture_handling();
+}
@@ -0,0 +1,5 @@
+use key_listener::hotkey::hotkey_handling;
+
+fn main() {
+    hotkey_handling();
+}
@@ -0,0 +1,5 @@
+use key_listener::sequence::sequence_handling;
+
+fn main() {
+    sequence_handling();
+}
@@ -0,0 +1,5 @@
+use key_listener::stats::stats_handling;
+
+fn main() {
+    stats_handling();
+}
@@ -0,0 +1,5 @@
+use key_listener::text::text_handling;
+
+fn main() {
+    text_handling();
+}
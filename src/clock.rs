@@ -0,0 +1,267 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use rdev::Event;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Reference point of the monotonic clock
+static PROCESS_START: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+thread_local! {
+    // Format used by `Timestamp` while a sink serializes a record
+    static TIME_FORMAT: Cell<TimeFormat> = const { Cell::new(TimeFormat::EpochMillis) };
+
+    // Clock of the event stream being handled on this thread
+    static EVENT_CLOCK: Cell<EventClock> = const { Cell::new(EventClock { replay: false, anchor: None, last: Duration::ZERO }) };
+}
+
+/// How wall-clock timestamps are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeFormat {
+    /// Integer milliseconds since the Unix epoch.
+    #[default]
+    EpochMillis,
+    /// Integer microseconds since the Unix epoch.
+    EpochMicros,
+    /// RFC 3339 string in UTC with microsecond precision.
+    Rfc3339,
+}
+
+impl TimeFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeFormat::EpochMillis => "ms",
+            TimeFormat::EpochMicros => "us",
+            TimeFormat::Rfc3339 => "rfc3339",
+        }
+    }
+}
+
+impl fmt::Display for TimeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for TimeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ms" | "millis" => Ok(TimeFormat::EpochMillis),
+            "us" | "µs" | "micros" => Ok(TimeFormat::EpochMicros),
+            "rfc3339" => Ok(TimeFormat::Rfc3339),
+            _ => Err(format!("Unknown time format: {} (expected ms, us or rfc3339)", s)),
+        }
+    }
+}
+
+/// Serializes everything inside `f` with timestamps written as `format`, and
+/// deserializes integer timestamps inside `f` as that format.
+pub fn with_time_format<R>(format: TimeFormat, f: impl FnOnce() -> R) -> R {
+    let previous = TIME_FORMAT.replace(format);
+    let result = f();
+    TIME_FORMAT.set(previous);
+    result
+}
+
+/// Starts the monotonic clock. Called once at startup so `monotonic_us`
+/// counts from process start rather than from the first event.
+pub fn start() {
+    LazyLock::force(&PROCESS_START);
+}
+
+/// Time since the monotonic clock started.
+pub fn monotonic() -> Duration {
+    PROCESS_START.elapsed()
+}
+
+/// Places the events of one stream on the monotonic clock.
+///
+/// Live events are read off the monotonic clock as they are received, so a
+/// wall clock stepped by NTP moves neither their readings nor the durations
+/// between them. A replay has no such moment: its first event is placed at the
+/// monotonic reading when it is handled, and every later one at its distance
+/// from the first by its own time, so a replay keeps the timing it was
+/// recorded with however fast it runs.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EventClock {
+    replay: bool,
+    anchor: Option<(SystemTime, Duration)>, // An event time and its reading: the first for a replay, the latest otherwise
+    last: Duration,
+}
+
+impl EventClock {
+    /// The clock of live input.
+    pub fn live() -> Self {
+        Self::default()
+    }
+
+    /// The clock of a replay, which runs on the times its events carry.
+    pub fn replay() -> Self {
+        EventClock { replay: true, ..Self::default() }
+    }
+
+    /// The reading for an event that happened at `time`, never behind an
+    /// earlier one.
+    pub fn reading(&mut self, time: SystemTime) -> Duration {
+        // Whole microseconds, so stamps map back onto the times they came from
+        let received = Duration::from_micros(monotonic().as_micros() as u64);
+        let reading = if self.replay {
+            let (anchor_time, anchor_reading) = *self.anchor.get_or_insert((time, received));
+            anchor_reading + time.duration_since(anchor_time).unwrap_or_default()
+        } else {
+            self.anchor = Some((time, received));
+            received
+        };
+        self.last = self.last.max(reading);
+        self.last
+    }
+
+    /// The current reading. A replay stands still at its latest event, since
+    /// nothing happens between its events but what they say.
+    pub fn now(&self) -> Duration {
+        if self.replay { self.last } else { self.last.max(monotonic()) }
+    }
+
+    /// The wall-clock time matching `reading`.
+    pub fn wall_time(&self, reading: Duration) -> SystemTime {
+        let (anchor_time, anchor_reading) = self.anchor.unwrap_or_else(|| (SystemTime::now(), monotonic()));
+        if reading >= anchor_reading {
+            anchor_time + (reading - anchor_reading)
+        } else {
+            anchor_time - (anchor_reading - reading)
+        }
+    }
+}
+
+/// Runs `f` with `clock` as the clock of the stream being handled, which
+/// `EventTime` stamps are read from.
+pub fn with_event_clock<R>(clock: &mut EventClock, f: impl FnOnce() -> R) -> R {
+    let previous = EVENT_CLOCK.replace(*clock);
+    let result = f();
+    *clock = EVENT_CLOCK.replace(previous);
+    result
+}
+
+/// A wall-clock instant, written in the format the sink selects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp(pub SystemTime);
+
+impl Timestamp {
+    pub fn micros(&self) -> u64 {
+        self.0.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64
+    }
+
+    pub fn millis(&self) -> u64 {
+        self.micros() / 1000
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match TIME_FORMAT.get() {
+            TimeFormat::EpochMillis => serializer.serialize_u64(self.millis()),
+            TimeFormat::EpochMicros => serializer.serialize_u64(self.micros()),
+            TimeFormat::Rfc3339 => {
+                let time: DateTime<Utc> = self.0.into();
+                serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Micros, true))
+            }
+        }
+    }
+}
+
+// Integers are read in the thread's current format, since their unit is not in
+// the data; strings are always RFC 3339
+struct TimestampVisitor;
+
+impl Visitor<'_> for TimestampVisitor {
+    type Value = Timestamp;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an epoch timestamp or an RFC 3339 string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Timestamp, E> {
+        let since_epoch = match TIME_FORMAT.get() {
+            TimeFormat::EpochMicros => Duration::from_micros(value),
+            TimeFormat::EpochMillis | TimeFormat::Rfc3339 => Duration::from_millis(value),
+        };
+        Ok(Timestamp(UNIX_EPOCH + since_epoch))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Timestamp, E> {
+        let value = u64::try_from(value).map_err(|_| E::custom("timestamp before the Unix epoch"))?;
+        self.visit_u64(value)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Timestamp, E> {
+        let time = DateTime::parse_from_rfc3339(value).map_err(E::custom)?;
+        Ok(Timestamp(time.with_timezone(&Utc).into()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
+
+impl JsonSchema for Timestamp {
+    fn schema_name() -> Cow<'static, str> {
+        "Timestamp".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Epoch milliseconds, epoch microseconds or an RFC 3339 string, depending on --time-format",
+            "anyOf": [
+                { "type": "integer", "minimum": 0 },
+                { "type": "string", "format": "date-time" }
+            ]
+        })
+    }
+}
+
+// When an input event happened, by the wall clock and by the monotonic clock.
+// Flattened into the event models, hence no doc comment leaking into their schema.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy)]
+pub struct EventTime {
+    pub timestamp: Timestamp, // From the OS event
+    pub monotonic_us: u64, // Microseconds since process start when the event was received; never goes backwards
+}
+
+impl EventTime {
+    /// Stamps `event` with its own wall-clock time and its reading on the
+    /// stream's clock.
+    pub fn of(event: &Event) -> Self {
+        let reading = EVENT_CLOCK.with(|cell| {
+            let mut clock = cell.get();
+            let reading = clock.reading(event.time);
+            cell.set(clock);
+            reading
+        });
+        EventTime { timestamp: Timestamp(event.time), monotonic_us: reading.as_micros() as u64 }
+    }
+
+    /// Stamps the moment the stream's clock reads `reading`. Timeouts are
+    /// stamped at their deadline rather than when they were noticed, so the
+    /// record says when the timeout happened and a replay reports it as the
+    /// live input did.
+    pub fn at(reading: Duration) -> Self {
+        let time = EVENT_CLOCK.get().wall_time(reading);
+        EventTime { timestamp: Timestamp(time), monotonic_us: reading.as_micros() as u64 }
+    }
+
+    /// Stamps something happening now without an input event, such as the end
+    /// of the stream.
+    pub fn now() -> Self {
+        Self::at(EVENT_CLOCK.get().now())
+    }
+}
@@ -0,0 +1,50 @@
+use rdev::Event;
+use crate::clock::EventTime;
+use crate::direct::key_event;
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::mode::Mode;
+use crate::models::OutputEvent;
+use crate::modifiers::ModifierTracker;
+use crate::mouse::mouse_event;
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
+
+// Emits keyboard and mouse events from the same hook as one ordered stream
+#[derive(Debug, Default)]
+pub struct CombinedHandler {
+    modifiers: ModifierTracker,
+}
+
+impl Handler for CombinedHandler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        self.modifiers.update(event);
+        // Stamp once so keyboard and mouse records share the same clock
+        let time = EventTime::of(event);
+        let modifiers = self.modifiers.current();
+        let combined_event = key_event(event, time, modifiers)
+            .map(OutputEvent::from)
+            .or_else(|| mouse_event(event, time, modifiers));
+
+        if let Some(ce) = combined_event {
+            out.push(ce);
+        }
+    }
+
+    fn observe(&mut self, event: &Event) {
+        self.modifiers.update(event);
+    }
+}
+
+pub fn combined_handling() {
+    combined_handling_with(&Options::from_env());
+}
+
+pub fn combined_handling_with(options: &Options) {
+    let sink = open_stdout(options, &[Mode::Combined]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(CombinedHandler::default(), options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for input events: {}", error);
+    }
+    sink.finish();
+}
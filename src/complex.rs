@@ -1,106 +1,151 @@
-use rdev::{listen, Event, EventType, Key};
-use crate::models::{ComplexKeyEvent, KeyEvent};
-use chrono::Utc;
+use rdev::{Event, EventType, Key};
+use crate::clock::EventTime;
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::keycodes::{raw_code, scan_code};
+use crate::keys::{event_text, KeyName};
+use crate::mode::Mode;
+use crate::models::{ComplexKeyEvent, KeyEvent, OutputEvent};
+use crate::modifiers::ModifierTracker;
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
 use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
 
-pub fn complex_handling() {
-    // Shared state for pressed keys and the current combination string
-    let pressed_keys = Arc::new(Mutex::new(HashSet::<Key>::new()));
-    let current_combination = Arc::new(Mutex::new(Vec::<String>::new()));
+// The keys held down right now, in the order they were pressed. Shared with
+// hotkey mode so both see the same combination.
+#[derive(Debug, Default)]
+pub struct HeldKeys {
+    pressed_keys: HashSet<Key>,
+    combination: Vec<KeyName>,
+}
 
-    // Clone Arcs for the callback closure
-    let pressed_keys_clone = Arc::clone(&pressed_keys);
-    let current_combination_clone = Arc::clone(&current_combination);
+impl HeldKeys {
+    // Records a press; false for an auto-repeat of a key already held
+    pub fn press(&mut self, key: Key) -> bool {
+        let is_new_press = self.pressed_keys.insert(key);
+        let key_name = KeyName::from(key);
+        if is_new_press && !self.combination.contains(&key_name) {
+            self.combination.push(key_name);
+        }
+        is_new_press
+    }
 
-    let callback = move |event: Event| {
-        let mut pressed_keys = pressed_keys_clone.lock().unwrap();
-        let mut current_combination = current_combination_clone.lock().unwrap();
-        let current_time = Utc::now().timestamp_millis() as u64;
-        let was_empty = pressed_keys.is_empty();
+    // Records a release; false for a key that was not held, e.g. one pressed
+    // before the listener started
+    pub fn release(&mut self, key: Key) -> bool {
+        if !self.pressed_keys.remove(&key) {
+            return false;
+        }
+        let key_name = KeyName::from(key);
+        if let Some(index) = self.combination.iter().position(|k| *k == key_name) {
+            self.combination.remove(index);
+        }
+        true
+    }
 
-        match event.event_type {
-            EventType::KeyPress(key) => {
-                let key_str = format!("{:?}", key);
-                let is_new_press = pressed_keys.insert(key);
+    pub fn keys(&self) -> &[KeyName] {
+        &self.combination
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pressed_keys.is_empty()
+    }
+}
 
-                if is_new_press {
-                    // Add to string combination if not already present
-                    if !current_combination.contains(&key_str) {
-                         current_combination.push(key_str.clone());
-                    }
+// Reports every key plus the combination it forms with the keys already held
+#[derive(Debug, Default)]
+pub struct ComplexHandler {
+    held: HeldKeys,
+    modifiers: ModifierTracker,
+}
+
+impl Handler for ComplexHandler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        self.modifiers.update(event);
+        let modifiers = self.modifiers.current();
+        let held = &mut self.held;
+        let time = EventTime::of(event);
+        let text = event_text(event);
 
+        match event.event_type {
+            EventType::KeyPress(key) => {
+                let was_empty = held.is_empty();
+                if held.press(key) {
                     // Output direct key press event
                     let key_press_event = KeyEvent {
-                        key: key_str.clone(),
+                        key: key.into(),
                         pressed: true,
-                        event_type: "direct".to_string(),
-                        timestamp: current_time,
+                        text: text.clone(),
+                        scan_code: scan_code(key),
+                        raw_code: raw_code(key),
+                        repeat: false,
+                        repeat_count: None,
+                        modifiers,
+                        time,
                     };
-                    if let Ok(json) = serde_json::to_string(&key_press_event) {
-                        println!("{}", json);
-                    }
+                    out.push(key_press_event.into());
 
                     // Output complex event
                     let complex_event = ComplexKeyEvent {
-                        keys: current_combination.clone(),
-                        event_type: if was_empty { "press".to_string() } else { "combination".to_string() },
-                        timestamp: current_time,
+                        keys: held.keys().to_vec(),
+                        text,
+                        time,
                     };
-                    if let Ok(json) = serde_json::to_string(&complex_event) {
-                        println!("{}", json);
-                    }
+                    out.push(if was_empty {
+                        OutputEvent::CombinationPress(complex_event)
+                    } else {
+                        OutputEvent::Combination(complex_event)
+                    });
                 }
             }
             EventType::KeyRelease(key) => {
-                let key_str = format!("{:?}", key);
-                if pressed_keys.remove(&key) {
-                     // Remove from string combination
-                    if let Some(index) = current_combination.iter().position(|k| k == &key_str) {
-                        current_combination.remove(index);
-                    }
+                let combination = held.keys().to_vec();
+                if !held.release(key) {
+                    return;
+                }
 
-                    // Output direct key release event
-                    let key_release_event = KeyEvent {
-                        key: key_str,
-                        pressed: false,
-                        event_type: "direct".to_string(),
-                        timestamp: current_time,
-                    };
-                     if let Ok(json) = serde_json::to_string(&key_release_event) {
-                        println!("{}", json);
-                    }
+                // Output direct key release event
+                let key_release_event = KeyEvent {
+                    key: key.into(),
+                    pressed: false,
+                    text: text.clone(),
+                    scan_code: scan_code(key),
+                    raw_code: raw_code(key),
+                    repeat: false,
+                    repeat_count: None,
+                    modifiers,
+                    time,
+                };
+                out.push(key_release_event.into());
 
-                    // Output complex event only if it's the last key being released
-                    if pressed_keys.is_empty() && !current_combination.is_empty() {
-                         let complex_event = ComplexKeyEvent {
-                            keys: current_combination.clone(), // Should be empty now conceptually, but send last state
-                            event_type: "release".to_string(),
-                            timestamp: current_time,
-                        };
-                        if let Ok(json) = serde_json::to_string(&complex_event) {
-                            println!("{}", json);
-                        }
-                        // Clear combination string only after the final release event is sent
-                        current_combination.clear();
-                    } else if !pressed_keys.is_empty() {
-                         // If other keys are still pressed, send a combination update
-                         let complex_event = ComplexKeyEvent {
-                            keys: current_combination.clone(),
-                            event_type: "combination".to_string(),
-                            timestamp: current_time,
-                        };
-                        if let Ok(json) = serde_json::to_string(&complex_event) {
-                            println!("{}", json);
-                        }
-                    }
+                // The last key released ends the combination, reported as it
+                // last stood; otherwise report what is still held
+                if held.is_empty() {
+                    let complex_event = ComplexKeyEvent { keys: combination, text, time };
+                    out.push(OutputEvent::CombinationRelease(complex_event));
+                } else {
+                    let complex_event = ComplexKeyEvent { keys: held.keys().to_vec(), text, time };
+                    out.push(OutputEvent::Combination(complex_event));
                 }
             }
             _ => {} // Ignore other event types
         }
-    };
+    }
+
+    fn observe(&mut self, event: &Event) {
+        self.modifiers.update(event);
+    }
+}
+
+pub fn complex_handling() {
+    complex_handling_with(&Options::from_env());
+}
 
-    if let Err(error) = listen(callback) {
-        eprintln!("Error listening for keyboard events: {:?}", error);
+pub fn complex_handling_with(options: &Options) {
+    let sink = open_stdout(options, &[Mode::Complex]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(ComplexHandler::default(), options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for keyboard events: {}", error);
     }
+    sink.finish();
 }
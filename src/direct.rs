@@ -1,37 +1,103 @@
-use rdev::{listen, Event, EventType};
-use crate::models::KeyEvent;
-use chrono::Utc;
+use rdev::{Event, EventType, Key};
+use crate::clock::EventTime;
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::keycodes::{raw_code, scan_code};
+use crate::keys::event_text;
+use crate::mode::Mode;
+use crate::models::{KeyEvent, Modifiers, OutputEvent};
+use crate::modifiers::ModifierTracker;
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
+use std::collections::HashMap;
 
-// Callback function to process key events
-fn callback(event: Event) {
-    let current_time = Utc::now().timestamp_millis() as u64;
-    let key_event = match event.event_type {
+// Maps a key press or release to a `KeyEvent`, ignoring mouse events
+pub fn key_event(event: &Event, time: EventTime, modifiers: Modifiers) -> Option<KeyEvent> {
+    match event.event_type {
         EventType::KeyPress(key) => Some(KeyEvent {
-            key: format!("{:?}", key), // Use Debug representation of rdev::Key
+            key: key.into(),
             pressed: true,
-            event_type: "direct".to_string(),
-            timestamp: current_time,
+            text: event_text(event),
+            scan_code: scan_code(key),
+            raw_code: raw_code(key),
+            repeat: false,
+            repeat_count: None,
+            modifiers,
+            time,
         }),
         EventType::KeyRelease(key) => Some(KeyEvent {
-            key: format!("{:?}", key), // Use Debug representation of rdev::Key
+            key: key.into(),
             pressed: false,
-            event_type: "direct".to_string(),
-            timestamp: current_time,
+            text: event_text(event),
+            scan_code: scan_code(key),
+            raw_code: raw_code(key),
+            repeat: false,
+            repeat_count: None,
+            modifiers,
+            time,
         }),
-        // Ignore mouse events in this handler
         _ => None,
-    };
+    }
+}
+
+// Emits every key press and release, marking the presses the OS repeats while
+// a key is held, or dropping them with `suppress_repeats`
+#[derive(Debug, Default)]
+pub struct DirectHandler {
+    held: HashMap<Key, u32>, // Keys down and how often each has repeated
+    suppress_repeats: bool,
+    modifiers: ModifierTracker,
+}
+
+impl DirectHandler {
+    pub fn new(suppress_repeats: bool) -> Self {
+        DirectHandler { suppress_repeats, ..Self::default() }
+    }
+}
 
-    if let Some(ke) = key_event {
-        if let Ok(json) = serde_json::to_string(&ke) {
-            println!("{}", json);
+impl Handler for DirectHandler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        self.modifiers.update(event);
+        let Some(mut ke) = key_event(event, EventTime::of(event), self.modifiers.current()) else {
+            return;
+        };
+        match event.event_type {
+            EventType::KeyPress(key) => {
+                if let Some(repeats) = self.held.get_mut(&key) {
+                    *repeats += 1;
+                    if self.suppress_repeats {
+                        return;
+                    }
+                    ke.repeat = true;
+                    ke.repeat_count = Some(*repeats);
+                } else {
+                    self.held.insert(key, 0);
+                }
+            }
+            EventType::KeyRelease(key) => {
+                // How often the key repeated before this release, counting suppressed repeats
+                ke.repeat_count = self.held.remove(&key).filter(|repeats| *repeats > 0);
+            }
+            _ => {}
         }
+        out.push(ke.into());
+    }
+
+    fn observe(&mut self, event: &Event) {
+        self.modifiers.update(event);
     }
 }
 
 pub fn direct_handling() {
+    direct_handling_with(&Options::from_env());
+}
+
+pub fn direct_handling_with(options: &Options) {
     // This will block the thread and listen for events.
-    if let Err(error) = listen(callback) {
-        eprintln!("Error listening for keyboard events: {:?}", error);
+    let sink = open_stdout(options, &[Mode::Direct]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(DirectHandler::new(options.suppress_repeats), options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for keyboard events: {}", error);
     }
-}
\ No newline at end of file
+    sink.finish();
+}
@@ -0,0 +1,251 @@
+use enigo::{Axis, Coordinate, Direction, InputResult, Key};
+use rdev::{Event, EventType};
+use crate::backend::{EnigoBackend, InputBackend};
+use crate::combined::CombinedHandler;
+use crate::filter::{EventFilter, Filtered};
+use crate::handler::Handler;
+use crate::keys::KeyName;
+use crate::mode::Mode;
+use crate::models::Record;
+use crate::options::Options;
+use crate::simulation::{simulate_input, simulated_key};
+use crate::output::open_stdout;
+use crate::sink::EventSink;
+use crate::source::{EventSource, RdevSource, SourceError};
+use std::io;
+use std::process;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long after an injection the matching hook event may still arrive
+const INJECTION_GRACE: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expected {
+    KeyPress(Option<rdev::Key>), // `None` matches any key
+    KeyRelease(Option<rdev::Key>),
+    Button(rdev::Button, bool),
+    Move(i32, i32),
+    AnyMove,
+    Wheel(Axis),
+}
+
+/// Remembers what this process injected so the listener can recognize the
+/// resulting hook events.
+///
+/// rdev does not say whether an event was synthetic, so every injected
+/// action is registered as an expected event that the next matching hook
+/// event consumes. Typed text produces an unknown number of key events and
+/// instead opens a window during which all key events count as injected.
+#[derive(Debug, Default)]
+pub struct InjectionTracker {
+    pending: Vec<(Expected, Instant)>,
+    typing: bool,
+    typing_until: Option<Instant>,
+}
+
+impl InjectionTracker {
+    fn expect(&mut self, expected: Expected) {
+        self.pending.push((expected, Instant::now() + INJECTION_GRACE));
+    }
+
+    fn begin_typing(&mut self) {
+        self.typing = true;
+    }
+
+    fn end_typing(&mut self) {
+        self.typing = false;
+        self.typing_until = Some(Instant::now() + INJECTION_GRACE);
+    }
+
+    /// Whether `event` is the echo of something this process injected.
+    pub fn is_injected(&mut self, event: &Event) -> bool {
+        let now = Instant::now();
+        self.pending.retain(|(_, deadline)| *deadline > now);
+
+        let is_key = matches!(event.event_type, EventType::KeyPress(_) | EventType::KeyRelease(_));
+        if is_key && (self.typing || self.typing_until.is_some_and(|until| until > now)) {
+            return true;
+        }
+
+        let matched = self.pending.iter().position(|(expected, _)| match (*expected, event.event_type) {
+            (Expected::KeyPress(expected), EventType::KeyPress(pressed)) => expected.is_none_or(|key| key == pressed),
+            (Expected::KeyRelease(expected), EventType::KeyRelease(released)) => expected.is_none_or(|key| key == released),
+            (Expected::Button(button, true), EventType::ButtonPress(pressed)) => button == pressed,
+            (Expected::Button(button, false), EventType::ButtonRelease(released)) => button == released,
+            (Expected::Move(x, y), EventType::MouseMove { x: event_x, y: event_y }) => {
+                (event_x - x as f64).abs() < 1.0 && (event_y - y as f64).abs() < 1.0
+            }
+            (Expected::AnyMove, EventType::MouseMove { .. }) => true,
+            (Expected::Wheel(Axis::Horizontal), EventType::Wheel { delta_x, .. }) => delta_x != 0,
+            (Expected::Wheel(Axis::Vertical), EventType::Wheel { delta_y, .. }) => delta_y != 0,
+            _ => false,
+        });
+
+        match matched {
+            Some(index) => {
+                self.pending.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// The key the hook reports for an injected one, if it reports one at all
+fn to_rdev_key(key: Key) -> Option<rdev::Key> {
+    KeyName::all().find(|name| simulated_key(*name) == Some(key)).and_then(KeyName::to_rdev)
+}
+
+fn to_rdev_button(button: enigo::Button) -> Option<rdev::Button> {
+    match button {
+        enigo::Button::Left => Some(rdev::Button::Left),
+        enigo::Button::Right => Some(rdev::Button::Right),
+        enigo::Button::Middle => Some(rdev::Button::Middle),
+        _ => None,
+    }
+}
+
+/// Forwards to another backend, registering every action with the tracker
+/// before it is performed so the echo cannot outrun the registration.
+pub struct TrackingBackend<B> {
+    inner: B,
+    tracker: Arc<Mutex<InjectionTracker>>,
+}
+
+impl<B: InputBackend> TrackingBackend<B> {
+    pub fn new(inner: B, tracker: Arc<Mutex<InjectionTracker>>) -> Self {
+        TrackingBackend { inner, tracker }
+    }
+
+    fn expect(&self, expected: Expected) {
+        self.tracker.lock().unwrap().expect(expected);
+    }
+}
+
+impl<B: InputBackend> InputBackend for TrackingBackend<B> {
+    fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
+        let rdev_key = match key {
+            // The key typing a character depends on the layout
+            Key::Unicode(_) => None,
+            key => match to_rdev_key(key) {
+                Some(rdev_key) => Some(rdev_key),
+                None => return self.inner.key(key, direction), // Never comes back
+            },
+        };
+        if matches!(direction, Direction::Press | Direction::Click) {
+            self.expect(Expected::KeyPress(rdev_key));
+        }
+        if matches!(direction, Direction::Release | Direction::Click) {
+            self.expect(Expected::KeyRelease(rdev_key));
+        }
+        self.inner.key(key, direction)
+    }
+
+    fn button(&mut self, button: enigo::Button, direction: Direction) -> InputResult<()> {
+        if let Some(button) = to_rdev_button(button) {
+            if matches!(direction, Direction::Press | Direction::Click) {
+                self.expect(Expected::Button(button, true));
+            }
+            if matches!(direction, Direction::Release | Direction::Click) {
+                self.expect(Expected::Button(button, false));
+            }
+        }
+        self.inner.button(button, direction)
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        match coordinate {
+            Coordinate::Abs => self.expect(Expected::Move(x, y)),
+            Coordinate::Rel => self.expect(Expected::AnyMove),
+        }
+        self.inner.move_mouse(x, y, coordinate)
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        // Each scroll unit arrives as its own wheel event
+        for _ in 0..length.unsigned_abs() {
+            self.expect(Expected::Wheel(axis));
+        }
+        self.inner.scroll(length, axis)
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        self.inner.location()
+    }
+
+    fn text(&mut self, text: &str) -> InputResult<()> {
+        self.tracker.lock().unwrap().begin_typing();
+        let result = self.inner.text(text);
+        self.tracker.lock().unwrap().end_typing();
+        result
+    }
+
+    fn now(&self) -> Duration {
+        self.inner.now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.inner.sleep(duration);
+    }
+}
+
+// Streams the combined keyboard + mouse events from `source`, marking or
+// dropping the ones recognized by `tracker`
+pub fn run_duplex<S>(source: &mut dyn EventSource, tracker: Arc<Mutex<InjectionTracker>>, suppress_injected: bool, filter: EventFilter, mut sink: S) -> Result<(), SourceError>
+where
+    S: EventSink + 'static,
+{
+    let mut handler = Filtered::new(CombinedHandler::default(), filter);
+    let mut out = Vec::new();
+    source.listen(Box::new(move |event: Event| {
+        let injected = tracker.lock().unwrap().is_injected(&event);
+        handler.handle(&event, &mut out);
+        for event in out.drain(..) {
+            if injected && suppress_injected {
+                continue;
+            }
+            if let Err(error) = sink.emit(Record { seq: None, mode: None, injected, event }) {
+                eprintln!("Error writing event: {}", error);
+            }
+        }
+    }))
+}
+
+pub fn duplex_handling() {
+    duplex_handling_with(&Options::from_env());
+}
+
+pub fn duplex_handling_with(options: &Options) {
+    let tracker = Arc::new(Mutex::new(InjectionTracker::default()));
+
+    // Simulation commands are executed on their own thread so the listener
+    // keeps streaming. The backend is created there too, and only once it is
+    // up does the listener start.
+    let simulation_tracker = Arc::clone(&tracker);
+    let format = options.format;
+    let (ready_sender, ready) = mpsc::channel();
+    thread::spawn(move || {
+        let backend = match EnigoBackend::new() {
+            Ok(backend) => backend,
+            Err(error) => {
+                let _ = ready_sender.send(Err(error));
+                return;
+            }
+        };
+        let _ = ready_sender.send(Ok(()));
+        let mut backend = TrackingBackend::new(backend, simulation_tracker);
+        simulate_input(&mut backend, io::stdin().lock(), format);
+    });
+    if let Ok(Err(error)) = ready.recv() {
+        eprintln!("Error initializing input simulation: {}", error);
+        process::exit(1);
+    }
+
+    let sink = open_stdout(options, &[Mode::Duplex]);
+    if let Err(error) = run_duplex(&mut RdevSource, tracker, options.suppress_injected, options.filter.clone(), sink.clone()) {
+        eprintln!("Error listening for input events: {}", error);
+    }
+    sink.finish();
+}
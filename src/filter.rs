@@ -0,0 +1,233 @@
+use rdev::{Event, EventType};
+use crate::handler::Handler;
+use crate::keys::{button_name, KeyName};
+use crate::models::OutputEvent;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Groups of keys a filter can name as `@class`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyClass {
+    /// Shift, Control, Alt, AltGr, Meta, CapsLock and Fn.
+    Modifier,
+    /// Letters and the digit row.
+    Alphanumeric,
+    /// F1 to F20.
+    Function,
+    /// Volume and media transport keys.
+    Media,
+    /// Arrows, Home, End, Page Up/Down, Insert and Delete.
+    Navigation,
+    /// The numeric keypad.
+    Keypad,
+}
+
+impl KeyClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyClass::Modifier => "modifier",
+            KeyClass::Alphanumeric => "alphanumeric",
+            KeyClass::Function => "function",
+            KeyClass::Media => "media",
+            KeyClass::Navigation => "navigation",
+            KeyClass::Keypad => "keypad",
+        }
+    }
+
+    pub fn contains(&self, key: KeyName) -> bool {
+        use KeyName::*;
+        match self {
+            KeyClass::Modifier => matches!(
+                key,
+                Alt | AltGr | ControlLeft | ControlRight | ShiftLeft | ShiftRight | MetaLeft | MetaRight | CapsLock | Function
+            ),
+            KeyClass::Alphanumeric => matches!(
+                key,
+                KeyA | KeyB | KeyC | KeyD | KeyE | KeyF | KeyG | KeyH | KeyI | KeyJ | KeyK | KeyL | KeyM | KeyN | KeyO
+                    | KeyP | KeyQ | KeyR | KeyS | KeyT | KeyU | KeyV | KeyW | KeyX | KeyY | KeyZ | Num0 | Num1 | Num2
+                    | Num3 | Num4 | Num5 | Num6 | Num7 | Num8 | Num9
+            ),
+            KeyClass::Function => matches!(
+                key,
+                F1 | F2 | F3 | F4 | F5 | F6 | F7 | F8 | F9 | F10 | F11 | F12 | F13 | F14 | F15 | F16 | F17 | F18 | F19 | F20
+            ),
+            KeyClass::Media => matches!(
+                key,
+                VolumeUp | VolumeDown | VolumeMute | MediaPlayPause | MediaNextTrack | MediaPrevTrack
+            ),
+            KeyClass::Navigation => matches!(
+                key,
+                UpArrow | DownArrow | LeftArrow | RightArrow | Home | End | PageUp | PageDown | Insert | Delete
+            ),
+            KeyClass::Keypad => matches!(
+                key,
+                Kp0 | Kp1 | Kp2 | Kp3 | Kp4 | Kp5 | Kp6 | Kp7 | Kp8 | Kp9 | KpReturn | KpMinus | KpPlus | KpMultiply
+                    | KpDivide | KpDelete | NumLock
+            ),
+        }
+    }
+}
+
+impl fmt::Display for KeyClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for KeyClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        // Plurals read naturally: @modifiers, @keypads
+        match name.strip_suffix('s').unwrap_or(&name) {
+            "modifier" => Ok(KeyClass::Modifier),
+            "alphanumeric" => Ok(KeyClass::Alphanumeric),
+            "function" => Ok(KeyClass::Function),
+            "media" => Ok(KeyClass::Media),
+            "navigation" => Ok(KeyClass::Navigation),
+            "keypad" => Ok(KeyClass::Keypad),
+            _ => Err(format!(
+                "Unknown key class: {} (expected modifier, alphanumeric, function, media, navigation or keypad)",
+                s
+            )),
+        }
+    }
+}
+
+/// One entry of a key filter: a key name, a `@class` or a glob over key names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyPattern {
+    Key(KeyName),
+    Class(KeyClass),
+    Glob(String),
+}
+
+impl KeyPattern {
+    pub fn matches(&self, key: KeyName) -> bool {
+        match self {
+            KeyPattern::Key(pattern_key) => *pattern_key == key,
+            KeyPattern::Class(class) => class.contains(key),
+            KeyPattern::Glob(glob) => glob_match(glob, &key.to_string()),
+        }
+    }
+}
+
+impl FromStr for KeyPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(class) = s.strip_prefix('@') {
+            return class.parse().map(KeyPattern::Class);
+        }
+        if s.contains(['*', '?']) {
+            return Ok(KeyPattern::Glob(s.to_string()));
+        }
+        s.parse().map(KeyPattern::Key)
+    }
+}
+
+// Case-insensitive match where `*` stands for any run of characters and `?` for one
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Pattern position after the last `*` and the text position it is matched up to
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p + 1, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `*` swallow one more character
+            backtrack = Some((star_p, star_t + 1));
+            p = star_p;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Decides which key and mouse button events reach the listener modes.
+///
+/// An include list that is `Some` lets through only what it matches, even
+/// when empty, so a filter whose patterns all failed to parse drops
+/// everything rather than nothing. Excludes apply after includes. Mouse moves
+/// and wheel events are never filtered.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub include_keys: Option<Vec<KeyPattern>>,
+    pub exclude_keys: Vec<KeyPattern>,
+    pub include_buttons: Option<Vec<String>>, // Globs over button names ("left", "button_8", ...)
+    pub exclude_buttons: Vec<String>,
+}
+
+impl EventFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include_keys.is_none() && self.exclude_keys.is_empty() && self.include_buttons.is_none() && self.exclude_buttons.is_empty()
+    }
+
+    pub fn allows_key(&self, key: KeyName) -> bool {
+        let included = self.include_keys.as_ref().is_none_or(|patterns| patterns.iter().any(|pattern| pattern.matches(key)));
+        included && !self.exclude_keys.iter().any(|pattern| pattern.matches(key))
+    }
+
+    pub fn allows_button(&self, name: &str) -> bool {
+        let included = self.include_buttons.as_ref().is_none_or(|globs| globs.iter().any(|glob| glob_match(glob, name)));
+        included && !self.exclude_buttons.iter().any(|glob| glob_match(glob, name))
+    }
+
+    pub fn allows(&self, event: &Event) -> bool {
+        match event.event_type {
+            EventType::KeyPress(key) | EventType::KeyRelease(key) => self.allows_key(key.into()),
+            EventType::ButtonPress(button) | EventType::ButtonRelease(button) => self.allows_button(&button_name(button)),
+            EventType::MouseMove { .. } | EventType::Wheel { .. } => true,
+        }
+    }
+}
+
+/// Runs `inner` on the events `filter` allows, so every mode sees the same
+/// filtered input: a dropped key never joins a combination or a sequence.
+pub struct Filtered<H> {
+    inner: H,
+    filter: EventFilter,
+}
+
+impl<H: Handler> Filtered<H> {
+    pub fn new(inner: H, filter: EventFilter) -> Self {
+        Filtered { inner, filter }
+    }
+}
+
+impl<H: Handler> Handler for Filtered<H> {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        if self.filter.allows(event) {
+            self.inner.handle(event, out);
+        } else {
+            self.inner.observe(event);
+        }
+    }
+
+    fn observe(&mut self, event: &Event) {
+        self.inner.observe(event);
+    }
+
+    fn deadline(&self) -> Option<Duration> {
+        self.inner.deadline()
+    }
+
+    fn expire(&mut self, now: Duration, out: &mut Vec<OutputEvent>) {
+        self.inner.expire(now, out);
+    }
+
+    fn finish(&mut self, out: &mut Vec<OutputEvent>) {
+        self.inner.finish(out);
+    }
+}
@@ -0,0 +1,108 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::str::FromStr;
+
+// Binary frames are a 4-byte big-endian payload length followed by the payload
+const FRAME_HEADER_LEN: usize = 4;
+// Refuses frames larger than this instead of allocating whatever a corrupt header claims
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Wire encoding for records on stdout and simulation commands on stdin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// One JSON document per line.
+    #[default]
+    Jsonl,
+    /// Length-prefixed MessagePack frames, with structs encoded as maps.
+    MessagePack,
+    /// Length-prefixed CBOR frames.
+    Cbor,
+}
+
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Jsonl => "jsonl",
+            Format::MessagePack => "msgpack",
+            Format::Cbor => "cbor",
+        }
+    }
+
+    /// Writes one message: a JSON line, or a single frame for the binary formats.
+    pub fn write<T: Serialize>(&self, writer: &mut impl Write, message: &T) -> io::Result<()> {
+        let payload = match self {
+            Format::Jsonl => {
+                serde_json::to_writer(&mut *writer, message)?;
+                return writer.write_all(b"\n");
+            }
+            Format::MessagePack => rmp_serde::to_vec_named(message).map_err(io::Error::other)?,
+            Format::Cbor => {
+                let mut payload = Vec::new();
+                ciborium::into_writer(message, &mut payload).map_err(io::Error::other)?;
+                payload
+            }
+        };
+
+        let len = u32::try_from(payload.len()).map_err(|_| io::Error::new(ErrorKind::InvalidData, "frame too large"))?;
+        writer.write_all(&len.to_be_bytes())?;
+        writer.write_all(&payload)
+    }
+
+    /// Decodes one message previously split off by `read_message`.
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, String> {
+        match self {
+            Format::Jsonl => serde_json::from_slice(bytes).map_err(|error| error.to_string()),
+            Format::MessagePack => rmp_serde::from_slice(bytes).map_err(|error| error.to_string()),
+            Format::Cbor => ciborium::from_reader(bytes).map_err(|error| error.to_string()),
+        }
+    }
+
+    /// Reads the raw bytes of the next message: a line for JSONL, a frame
+    /// payload otherwise. `Ok(None)` at a clean end of input.
+    pub fn read_message(&self, reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+        if *self == Format::Jsonl {
+            let mut line = Vec::new();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(None);
+            }
+            return Ok(Some(line));
+        }
+
+        // Only an input ending between frames ends cleanly; a partial header is truncated
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        let len = u32::from_be_bytes(header) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("frame of {} bytes exceeds the limit", len)));
+        }
+
+        let mut payload = vec![0; len];
+        reader.read_exact(&mut payload)?;
+        Ok(Some(payload))
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "jsonl" | "json" => Ok(Format::Jsonl),
+            "msgpack" | "messagepack" => Ok(Format::MessagePack),
+            "cbor" => Ok(Format::Cbor),
+            _ => Err(format!("Unknown format: {} (expected jsonl, msgpack or cbor)", s)),
+        }
+    }
+}
+
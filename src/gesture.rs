@@ -0,0 +1,208 @@
+use rdev::{Event, EventType};
+use crate::clock::EventTime;
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::keys::KeyName;
+use crate::mode::Mode;
+use crate::models::{GestureEvent, GestureKind, OutputEvent};
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Timing that separates taps, holds and long presses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureThresholds {
+    /// Longest press that still counts as a tap.
+    pub tap_max: Duration,
+    /// Longest pause between taps of the same key that continues a run.
+    pub tap_gap: Duration,
+    /// How long a key must be held to count as a long press.
+    pub long_press: Duration,
+}
+
+impl Default for GestureThresholds {
+    fn default() -> Self {
+        GestureThresholds {
+            tap_max: Duration::from_millis(200),
+            tap_gap: Duration::from_millis(250),
+            long_press: Duration::from_millis(500),
+        }
+    }
+}
+
+impl GestureThresholds {
+    /// The defaults, overridden by `--tap-max`, `--tap-gap` and `--long-press`.
+    pub fn from_options(options: &Options) -> Self {
+        let defaults = Self::default();
+        let thresholds = GestureThresholds {
+            tap_max: options.tap_max.unwrap_or(defaults.tap_max),
+            tap_gap: options.tap_gap.unwrap_or(defaults.tap_gap),
+            long_press: options.long_press.unwrap_or(defaults.long_press),
+        };
+        if thresholds.long_press <= thresholds.tap_max {
+            eprintln!("--long-press is not longer than --tap-max, so no press will be reported as a hold");
+        }
+        thresholds
+    }
+}
+
+// What one key has been doing, on the monotonic clock
+#[derive(Debug, Default)]
+struct KeyState {
+    pressed_at: Option<Duration>, // While the key is down
+    long_pressed: bool, // The current press was already reported
+    taps: u32, // Taps in the run waiting for its gap to pass
+    first_tap_at: Duration,
+    last_release_at: Duration,
+}
+
+impl KeyState {
+    fn deadline(&self, thresholds: &GestureThresholds) -> Option<Duration> {
+        match self.pressed_at {
+            Some(pressed_at) if !self.long_pressed => Some(pressed_at + thresholds.long_press),
+            Some(_) => None,
+            None if self.taps > 0 => Some(self.last_release_at + thresholds.tap_gap),
+            None => None,
+        }
+    }
+}
+
+// Classifies each key's presses on its own. Short presses of the same key in
+// quick succession add up to one tap run, reported once the gap after the
+// last tap passes or another key is pressed; a press past the long-press
+// threshold is reported without waiting for its release.
+#[derive(Debug, Default)]
+pub struct GestureHandler {
+    thresholds: GestureThresholds,
+    keys: HashMap<KeyName, KeyState>,
+}
+
+impl GestureHandler {
+    pub fn new(thresholds: GestureThresholds) -> Self {
+        GestureHandler { thresholds, keys: HashMap::new() }
+    }
+
+    // Reports the tap run of `key`, if any
+    fn flush_taps(&mut self, key: KeyName, time: EventTime, out: &mut Vec<OutputEvent>) {
+        let Some(state) = self.keys.get_mut(&key) else {
+            return;
+        };
+        if state.taps == 0 {
+            return;
+        }
+        let gesture = match state.taps {
+            1 => GestureKind::Tap,
+            2 => GestureKind::DoubleTap,
+            _ => GestureKind::MultiTap,
+        };
+        let duration = state.last_release_at.saturating_sub(state.first_tap_at);
+        out.push(GestureEvent { key, gesture, count: state.taps, duration_ms: duration.as_millis() as u64, time }.into());
+        state.taps = 0;
+    }
+
+    fn press(&mut self, key: KeyName, now: Duration, time: EventTime, out: &mut Vec<OutputEvent>) {
+        // Another key ends the tap runs of all others
+        let others: Vec<KeyName> = self.keys.iter().filter(|(other, state)| **other != key && state.taps > 0).map(|(other, _)| *other).collect();
+        for other in others {
+            self.flush_taps(other, time, out);
+        }
+
+        let state = self.keys.entry(key).or_default();
+        // Auto-repeat
+        if state.pressed_at.is_some() {
+            return;
+        }
+        state.pressed_at = Some(now);
+        state.long_pressed = false;
+    }
+
+    fn release(&mut self, key: KeyName, now: Duration, time: EventTime, out: &mut Vec<OutputEvent>) {
+        let Some(state) = self.keys.get_mut(&key) else {
+            return;
+        };
+        let Some(pressed_at) = state.pressed_at.take() else {
+            return;
+        };
+        if state.long_pressed {
+            return;
+        }
+
+        let held = now.saturating_sub(pressed_at);
+        if held <= self.thresholds.tap_max {
+            if state.taps == 0 {
+                state.first_tap_at = pressed_at;
+            }
+            state.taps += 1;
+            state.last_release_at = now;
+            return;
+        }
+
+        self.flush_taps(key, time, out);
+        out.push(GestureEvent { key, gesture: GestureKind::Hold, count: 1, duration_ms: held.as_millis() as u64, time }.into());
+    }
+}
+
+impl Handler for GestureHandler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        let time = EventTime::of(event);
+        let now = Duration::from_micros(time.monotonic_us);
+        match event.event_type {
+            EventType::KeyPress(key) => self.press(key.into(), now, time, out),
+            EventType::KeyRelease(key) => self.release(key.into(), now, time, out),
+            _ => {} // Ignore other event types
+        }
+    }
+
+    fn deadline(&self) -> Option<Duration> {
+        self.keys.values().filter_map(|state| state.deadline(&self.thresholds)).min()
+    }
+
+    fn expire(&mut self, now: Duration, out: &mut Vec<OutputEvent>) {
+        let mut due: Vec<(Duration, KeyName)> = self
+            .keys
+            .iter()
+            .filter_map(|(key, state)| state.deadline(&self.thresholds).filter(|deadline| *deadline <= now).map(|deadline| (deadline, *key)))
+            .collect();
+        due.sort_by_key(|(deadline, _)| *deadline);
+
+        for (deadline, key) in due {
+            let time = EventTime::at(deadline);
+            // Taps before a long press come first
+            self.flush_taps(key, time, out);
+            let state = self.keys.get_mut(&key).unwrap();
+            if let Some(pressed_at) = state.pressed_at {
+                state.long_pressed = true;
+                let held = deadline.saturating_sub(pressed_at);
+                out.push(GestureEvent { key, gesture: GestureKind::LongPress, count: 1, duration_ms: held.as_millis() as u64, time }.into());
+            }
+        }
+    }
+
+    // Tap runs still waiting for their gap are complete once the stream ends;
+    // a key still down was not classified yet and is left out
+    fn finish(&mut self, out: &mut Vec<OutputEvent>) {
+        let mut pending: Vec<(Duration, KeyName)> =
+            self.keys.iter().filter(|(_, state)| state.taps > 0).map(|(key, state)| (state.last_release_at, *key)).collect();
+        pending.sort_by_key(|(last_release_at, _)| *last_release_at);
+
+        let time = EventTime::now();
+        for (_, key) in pending {
+            self.flush_taps(key, time, out);
+        }
+    }
+}
+
+pub fn gesture_handling() {
+    gesture_handling_with(&Options::from_env());
+}
+
+pub fn gesture_handling_with(options: &Options) {
+    let handler = GestureHandler::new(GestureThresholds::from_options(options));
+    let sink = open_stdout(options, &[Mode::Gesture]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(handler, options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for keyboard events: {}", error);
+    }
+    sink.finish();
+}
@@ -0,0 +1,222 @@
+use rdev::Event;
+use crate::clock::{self, EventClock, EventTime};
+use crate::models::{OutputEvent, Record};
+use crate::sink::EventSink;
+use crate::source::{EventSource, SourceError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, TryLockError, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Longest the timer thread sleeps before looking for a new deadline
+const TIMER_TICK: Duration = Duration::from_millis(10);
+
+// How long an interrupt waits for a handler busy with an event before giving up on its final output
+const FINISH_WAIT: Duration = Duration::from_secs(1);
+
+// Drivers currently running, so an interrupt can finish their handlers
+static RUNNING: Mutex<Vec<Weak<Mutex<dyn Finish + Send>>>> = Mutex::new(Vec::new());
+
+/// The state machine behind a listener mode, driven one event at a time.
+///
+/// Anything the mode wants to report is pushed onto `out`; `run` forwards it
+/// to the sink. Modes with timeouts also report a `deadline`, and get
+/// `expire` called once it passes even if no further event arrives.
+pub trait Handler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>);
+
+    /// Sees an event a filter kept from `handle`, so state such as the held
+    /// modifiers stays right without reporting anything.
+    fn observe(&mut self, _event: &Event) {}
+
+    /// When `expire` is next due, as a reading of the stream's `EventClock`.
+    fn deadline(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Reports whatever timed out by `now`, a reading of the stream's `EventClock`,
+    /// each stamped with `EventTime::at` its deadline.
+    fn expire(&mut self, _now: Duration, _out: &mut Vec<OutputEvent>) {}
+
+    /// Reports whatever is left once the stream ends, including on Ctrl+C.
+    fn finish(&mut self, _out: &mut Vec<OutputEvent>) {}
+}
+
+impl<H: Handler + ?Sized> Handler for Box<H> {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        (**self).handle(event, out);
+    }
+
+    fn observe(&mut self, event: &Event) {
+        (**self).observe(event);
+    }
+
+    fn deadline(&self) -> Option<Duration> {
+        (**self).deadline()
+    }
+
+    fn expire(&mut self, now: Duration, out: &mut Vec<OutputEvent>) {
+        (**self).expire(now, out);
+    }
+
+    fn finish(&mut self, out: &mut Vec<OutputEvent>) {
+        (**self).finish(out);
+    }
+}
+
+// Wraps pending output in records and writes them to the sink
+fn emit_all(sink: &mut dyn EventSink, mode: Option<&str>, out: &mut Vec<OutputEvent>) {
+    for event in out.drain(..) {
+        let record = Record { seq: None, mode: mode.map(str::to_string), injected: false, event };
+        if let Err(error) = sink.emit(record) {
+            eprintln!("Error writing event: {}", error);
+        }
+    }
+}
+
+// Handlers and sink shared by the event callback and the timer thread
+struct Driver<S> {
+    handlers: Vec<(Option<String>, Box<dyn Handler + Send>)>,
+    sink: S,
+    out: Vec<OutputEvent>,
+    clock: EventClock,
+    finished: bool,
+}
+
+trait Finish {
+    fn finish(&mut self);
+}
+
+impl<S: EventSink> Driver<S> {
+    // Runs `f` on this stream's clock
+    fn on_clock<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let mut clock = self.clock;
+        let result = clock::with_event_clock(&mut clock, || f(self));
+        self.clock = clock;
+        result
+    }
+
+    fn handle(&mut self, event: &Event) {
+        self.on_clock(|driver| {
+            // Timeouts that passed before this event come first
+            driver.expire(Duration::from_micros(EventTime::of(event).monotonic_us));
+            for (mode, handler) in driver.handlers.iter_mut() {
+                handler.handle(event, &mut driver.out);
+                emit_all(&mut driver.sink, mode.as_deref(), &mut driver.out);
+            }
+        });
+    }
+
+    // Expires whatever is due by the stream's clock now
+    fn tick(&mut self) -> Option<Duration> {
+        self.on_clock(|driver| {
+            let now = Duration::from_micros(EventTime::now().monotonic_us);
+            driver.expire(now);
+            driver.deadline().map(|deadline| deadline.saturating_sub(now))
+        })
+    }
+
+    fn expire(&mut self, now: Duration) {
+        for (mode, handler) in self.handlers.iter_mut() {
+            if handler.deadline().is_some_and(|deadline| deadline <= now) {
+                handler.expire(now, &mut self.out);
+                emit_all(&mut self.sink, mode.as_deref(), &mut self.out);
+            }
+        }
+    }
+
+    fn deadline(&self) -> Option<Duration> {
+        self.handlers.iter().filter_map(|(_, handler)| handler.deadline()).min()
+    }
+}
+
+impl<S: EventSink> Finish for Driver<S> {
+    fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        self.on_clock(|driver| {
+            for (mode, handler) in driver.handlers.iter_mut() {
+                handler.finish(&mut driver.out);
+                emit_all(&mut driver.sink, mode.as_deref(), &mut driver.out);
+            }
+        });
+    }
+}
+
+/// Gives the handlers of every running `run` their `finish` call, for an
+/// interrupt that is about to end the process. A handler stuck on an event,
+/// e.g. behind a full pipe, is skipped after a second.
+pub fn finish_running() {
+    let drivers: Vec<_> = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter().filter_map(Weak::upgrade).collect();
+    let deadline = Instant::now() + FINISH_WAIT;
+    for driver in drivers {
+        loop {
+            match driver.try_lock() {
+                Ok(mut driver) => break driver.finish(),
+                Err(TryLockError::Poisoned(poisoned)) => break poisoned.into_inner().finish(),
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                Err(TryLockError::WouldBlock) => {
+                    eprintln!("Error finishing handlers: output is stalled");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Feeds every event from `source` to the handlers. Timeouts of live input
+// also expire on a timer thread while the source is quiet; a replay has no
+// time between its events, so its timeouts expire as later events pass them.
+pub(crate) fn drive<S>(source: &mut dyn EventSource, handlers: Vec<(Option<String>, Box<dyn Handler + Send>)>, sink: S) -> Result<(), SourceError>
+where
+    S: EventSink + Send + 'static,
+{
+    let clock = if source.is_live() { EventClock::live() } else { EventClock::replay() };
+    let driver = Arc::new(Mutex::new(Driver { handlers, sink, out: Vec::new(), clock, finished: false }));
+    let done = Arc::new(AtomicBool::new(false));
+    {
+        let mut running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        running.retain(|driver| driver.strong_count() > 0);
+        let finish: Arc<Mutex<dyn Finish + Send>> = driver.clone();
+        running.push(Arc::downgrade(&finish));
+    }
+
+    if source.is_live() {
+        let timer_driver = Arc::clone(&driver);
+        let timer_done = Arc::clone(&done);
+        thread::spawn(move || {
+            while !timer_done.load(Ordering::SeqCst) {
+                let wait = timer_driver.lock().unwrap().tick().map_or(TIMER_TICK, |wait| wait.min(TIMER_TICK));
+                thread::sleep(wait);
+            }
+        });
+    }
+
+    let event_driver = Arc::clone(&driver);
+    let result = source.listen(Box::new(move |event: Event| {
+        event_driver.lock().unwrap().handle(&event);
+    }));
+    done.store(true, Ordering::SeqCst);
+    driver.lock().unwrap().finish();
+    result
+}
+
+// Drives `handler` with every event produced by `source`, writing its output to `sink`
+pub fn run<H, S>(source: &mut dyn EventSource, handler: H, sink: S) -> Result<(), SourceError>
+where
+    H: Handler + Send + 'static,
+    S: EventSink + Send + 'static,
+{
+    drive(source, vec![(None, Box::new(handler))], sink)
+}
+
+// Fans every event out to several named modes over a single source. Records
+// are tagged with the mode that produced them and interleaved in event order.
+pub fn run_modes<S>(source: &mut dyn EventSource, handlers: Vec<(String, Box<dyn Handler + Send>)>, sink: S) -> Result<(), SourceError>
+where
+    S: EventSink + Send + 'static,
+{
+    drive(source, handlers.into_iter().map(|(mode, handler)| (Some(mode), handler)).collect(), sink)
+}
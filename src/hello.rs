@@ -0,0 +1,69 @@
+use crate::clock;
+use crate::keys::KeyName;
+use crate::mode::Mode;
+use crate::models::{Hello, OutputEvent};
+use crate::sink::EventSink;
+use std::collections::BTreeMap;
+
+/// Version of the record format written to stdout and the commands read from
+/// stdin. Bumped whenever a change could break an existing consumer.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+// Cargo features this binary was built with
+fn compiled_features() -> Vec<String> {
+    let features = [
+        ("direct", cfg!(feature = "direct")),
+        ("complex", cfg!(feature = "complex")),
+        ("hold_and_release", cfg!(feature = "hold_and_release")),
+        ("simulation", cfg!(feature = "simulation")),
+        ("mouse", cfg!(feature = "mouse")),
+        ("combined", cfg!(feature = "combined")),
+        ("duplex", cfg!(feature = "duplex")),
+        ("hotkey", cfg!(feature = "hotkey")),
+        ("sequence", cfg!(feature = "sequence")),
+        ("gesture", cfg!(feature = "gesture")),
+        ("text", cfg!(feature = "text")),
+        ("stats", cfg!(feature = "stats")),
+        ("no-simulation", cfg!(feature = "no-simulation")),
+    ];
+    features.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| name.to_string()).collect()
+}
+
+// Simulation commands accepted on stdin, keyed by their `event_type`
+#[cfg(feature = "simulation")]
+fn simulation_actions() -> Option<BTreeMap<String, Vec<String>>> {
+    use crate::simulation::{KEY_ACTIONS, MOUSE_ACTIONS};
+
+    let to_strings = |actions: &[&str]| actions.iter().map(|action| action.to_string()).collect();
+    Some(BTreeMap::from([
+        ("key".to_string(), to_strings(KEY_ACTIONS)),
+        ("mouse".to_string(), to_strings(MOUSE_ACTIONS)),
+        ("text".to_string(), Vec::new()),
+    ]))
+}
+
+#[cfg(not(feature = "simulation"))]
+fn simulation_actions() -> Option<BTreeMap<String, Vec<String>>> {
+    None
+}
+
+/// Describes this build and the modes about to run.
+pub fn hello(modes: &[Mode]) -> Hello {
+    Hello {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        protocol_version: PROTOCOL_VERSION,
+        modes: modes.iter().map(|mode| mode.name().to_string()).collect(),
+        features: compiled_features(),
+        keys: KeyName::all().collect(),
+        actions: simulation_actions(),
+    }
+}
+
+// Writes the hello record that opens every output stream. Also starts the
+// monotonic clock, since this is the first thing every mode does.
+pub fn announce(sink: &mut dyn EventSink, modes: &[Mode]) {
+    clock::start();
+    if let Err(error) = sink.emit(OutputEvent::from(hello(modes)).into()) {
+        eprintln!("Error writing event: {}", error);
+    }
+}
@@ -1,75 +1,116 @@
-use rdev::{listen, Event, EventType, Key};
-use crate::models::KeySequenceEvent;
-use chrono::Utc;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
+use rdev::{Event, EventType, Key};
+use crate::clock::EventTime;
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::keys::KeyName;
+use crate::mode::Mode;
+use crate::models::{KeySequenceEvent, KeyTransition, OutputEvent};
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
+use std::collections::HashMap;
 
-pub fn hold_and_release_handling() {
-    // Shared state
-    let pressed_keys = Arc::new(Mutex::new(HashSet::<Key>::new()));
-    let keys_sequence = Arc::new(Mutex::new(Vec::<String>::new()));
-    let sequence_start_time = Arc::new(Mutex::new(None::<u64>));
+// Collects every key pressed until all of them are released again, and with
+// `timeline` every press and release along the way
+#[derive(Debug, Default)]
+pub struct HoldAndReleaseHandler {
+    pressed_keys: HashMap<Key, u64>, // Keys down and the monotonic time they went down
+    keys_sequence: Vec<KeyName>,
+    sequence_start_time: Option<EventTime>,
+    record_timeline: bool,
+    timeline: Vec<KeyTransition>,
+}
 
-    // Clone Arcs for the callback
-    let pressed_keys_clone = Arc::clone(&pressed_keys);
-    let keys_sequence_clone = Arc::clone(&keys_sequence);
-    let sequence_start_time_clone = Arc::clone(&sequence_start_time);
+impl HoldAndReleaseHandler {
+    pub fn new(record_timeline: bool) -> Self {
+        HoldAndReleaseHandler { record_timeline, ..Self::default() }
+    }
+}
 
-    let callback = move |event: Event| {
-        let mut pressed_keys = pressed_keys_clone.lock().unwrap();
-        let mut keys_sequence = keys_sequence_clone.lock().unwrap();
-        let mut start_time = sequence_start_time_clone.lock().unwrap();
-        let current_time = Utc::now().timestamp_millis() as u64;
+impl Handler for HoldAndReleaseHandler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        let pressed_keys = &mut self.pressed_keys;
+        let keys_sequence = &mut self.keys_sequence;
+        let start_time = &mut self.sequence_start_time;
+        let timeline = &mut self.timeline;
+        let current_time = EventTime::of(event);
 
         match event.event_type {
             EventType::KeyPress(key) => {
-                let key_str = format!("{:?}", key);
-                let is_new_press = pressed_keys.insert(key);
+                let key_name = KeyName::from(key);
+                let is_new_press = !pressed_keys.contains_key(&key);
 
                 if is_new_press {
+                    pressed_keys.insert(key, current_time.monotonic_us);
                     // Start sequence on first key press
-                    if start_time.is_none() {
-                        *start_time = Some(current_time);
-                    }
+                    let st = *start_time.get_or_insert(current_time);
                     // Add key to sequence if not already present
-                    if !keys_sequence.contains(&key_str) {
-                        keys_sequence.push(key_str);
+                    if !keys_sequence.contains(&key_name) {
+                        keys_sequence.push(key_name);
+                    }
+                    if self.record_timeline {
+                        timeline.push(KeyTransition {
+                            key: key_name,
+                            pressed: true,
+                            offset_us: current_time.monotonic_us.saturating_sub(st.monotonic_us),
+                            hold_us: None,
+                        });
                     }
                 }
             }
             EventType::KeyRelease(key) => {
-                if pressed_keys.remove(&key) {
-                    // Check if this was the last pressed key
-                    if pressed_keys.is_empty() {
-                        if let Some(st) = *start_time {
-                            let end_time = current_time;
-                            let duration = end_time - st;
+                // Keys pressed before the listener started are not part of any sequence
+                let Some(pressed_at) = pressed_keys.remove(&key) else {
+                    return;
+                };
+                if self.record_timeline
+                    && let Some(st) = *start_time
+                {
+                    timeline.push(KeyTransition {
+                        key: key.into(),
+                        pressed: false,
+                        offset_us: current_time.monotonic_us.saturating_sub(st.monotonic_us),
+                        hold_us: Some(current_time.monotonic_us.saturating_sub(pressed_at)),
+                    });
+                }
 
-                            let sequence_event = KeySequenceEvent {
-                                event_type: "key_sequence".to_string(),
-                                keys: keys_sequence.clone(),
-                                start_time: st,
-                                end_time,
-                                duration_ms: duration,
-                            };
+                // Check if this was the last pressed key
+                if pressed_keys.is_empty()
+                    && let Some(st) = *start_time
+                {
+                    let end_time = current_time;
+                    // The monotonic clock cannot make the duration negative
+                    let duration_us = end_time.monotonic_us.saturating_sub(st.monotonic_us);
 
-                            if let Ok(json) = serde_json::to_string(&sequence_event) {
-                                println!("{}", json);
-                            }
+                    let sequence_event = KeySequenceEvent {
+                        keys: keys_sequence.clone(),
+                        start_time: st.timestamp,
+                        end_time: end_time.timestamp,
+                        duration_ms: duration_us / 1000,
+                        duration_us,
+                        timeline: self.record_timeline.then(|| std::mem::take(timeline)),
+                    };
 
-                            // Reset sequence state
-                            keys_sequence.clear();
-                            *start_time = None;
-                        }
-                    }
+                    out.push(sequence_event.into());
+
+                    // Reset sequence state
+                    keys_sequence.clear();
+                    *start_time = None;
                 }
             }
             _ => {} // Ignore other event types
         }
-    };
-
-    if let Err(error) = listen(callback) {
-        eprintln!("Error listening for keyboard events: {:?}", error);
     }
 }
 
+pub fn hold_and_release_handling() {
+    hold_and_release_handling_with(&Options::from_env());
+}
+
+pub fn hold_and_release_handling_with(options: &Options) {
+    let sink = open_stdout(options, &[Mode::HoldAndRelease]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(HoldAndReleaseHandler::new(options.timeline), options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for keyboard events: {}", error);
+    }
+    sink.finish();
+}
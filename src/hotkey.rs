@@ -0,0 +1,272 @@
+use rdev::{Event, EventType};
+use crate::clock::EventTime;
+use crate::complex::HeldKeys;
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::keys::KeyName;
+use crate::mode::Mode;
+use crate::models::{HotkeyEvent, HotkeyPhase, OutputEvent};
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::mem;
+use std::str::FromStr;
+
+/// A modifier a hotkey can require. Either side of the keyboard satisfies it,
+/// so `Alt` takes both Alt keys; `AltGr` asks for the right one only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    AltGr,
+    Meta,
+}
+
+impl Modifier {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Modifier::Ctrl => "Ctrl",
+            Modifier::Shift => "Shift",
+            Modifier::Alt => "Alt",
+            Modifier::AltGr => "AltGr",
+            Modifier::Meta => "Meta",
+        }
+    }
+
+    /// The modifier `key` counts as, if any.
+    pub fn of(key: KeyName) -> Option<Modifier> {
+        match key {
+            KeyName::ControlLeft | KeyName::ControlRight => Some(Modifier::Ctrl),
+            KeyName::ShiftLeft | KeyName::ShiftRight => Some(Modifier::Shift),
+            KeyName::Alt | KeyName::AltGr => Some(Modifier::Alt),
+            KeyName::MetaLeft | KeyName::MetaRight => Some(Modifier::Meta),
+            _ => None,
+        }
+    }
+
+    // Whether holding `key` satisfies this modifier
+    fn is_held_by(&self, key: KeyName) -> bool {
+        match self {
+            Modifier::AltGr => key == KeyName::AltGr,
+            _ => Modifier::of(key) == Some(*self),
+        }
+    }
+}
+
+impl FromStr for Modifier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ctrl" | "control" => Ok(Modifier::Ctrl),
+            "shift" => Ok(Modifier::Shift),
+            "alt" | "option" => Ok(Modifier::Alt),
+            "altgr" => Ok(Modifier::AltGr),
+            "meta" | "super" | "win" | "cmd" | "command" => Ok(Modifier::Meta),
+            _ => Err(format!("Unknown modifier: {}", s)),
+        }
+    }
+}
+
+/// One key pressed while some modifiers are held, such as `Ctrl+S`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: BTreeSet<Modifier>,
+    pub key: KeyName,
+}
+
+impl Chord {
+    /// The chords typed by pressing `key` while `held` (which includes `key`)
+    /// is down, strictest first: with the right Alt key held, the `AltGr`
+    /// spelling comes before the `Alt` one. Empty if another non-modifier key
+    /// is held as well.
+    pub fn pressed(held: &[KeyName], key: KeyName) -> Vec<Chord> {
+        let mut modifiers = BTreeSet::new();
+        for held_key in held {
+            match Modifier::of(*held_key) {
+                Some(modifier) => {
+                    modifiers.insert(modifier);
+                }
+                None if *held_key == key => {}
+                None => return Vec::new(),
+            }
+        }
+        let chord = Chord { modifiers, key };
+        if !held.contains(&KeyName::AltGr) {
+            return vec![chord];
+        }
+
+        let mut strict = chord.clone();
+        if !held.contains(&KeyName::Alt) {
+            strict.modifiers.remove(&Modifier::Alt);
+        }
+        strict.modifiers.insert(Modifier::AltGr);
+        vec![strict, chord]
+    }
+
+    // Whether `held` still contains this chord, extra keys allowed
+    fn is_held(&self, held: &[KeyName]) -> bool {
+        held.contains(&self.key) && self.modifiers.iter().all(|modifier| held.iter().any(|key| modifier.is_held_by(*key)))
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier.name())?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key_part = parts.pop().filter(|part| !part.is_empty()).ok_or_else(|| format!("No key in {}", s))?;
+        let key: KeyName = key_part.parse()?;
+        if Modifier::of(key).is_some() {
+            return Err(format!("{} must end with a key that is not a modifier", s));
+        }
+        let modifiers = parts.iter().map(|part| part.parse()).collect::<Result<_, _>>()?;
+        Ok(Chord { modifiers, key })
+    }
+}
+
+/// A named chord such as `save = Ctrl+S`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hotkey {
+    pub name: String,
+    pub chord: Chord,
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.name, self.chord)
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, chord) = s.split_once('=').ok_or_else(|| format!("Expected name = Keys, got: {}", s))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("Hotkey without a name: {}", s));
+        }
+        let chord = chord.parse().map_err(|error| format!("Hotkey {}: {}", name, error))?;
+        Ok(Hotkey { name: name.to_string(), chord })
+    }
+}
+
+// Parses `name = Keys` bindings, reporting and skipping the ones that do not parse
+pub fn parse_hotkeys(bindings: &[String]) -> Vec<Hotkey> {
+    bindings
+        .iter()
+        .filter_map(|binding| match binding.parse() {
+            Ok(hotkey) => Some(hotkey),
+            Err(error) => {
+                eprintln!("Invalid hotkey: {}", error);
+                None
+            }
+        })
+        .collect()
+}
+
+// The bindings given in `options`, or why hotkey mode cannot run without any
+pub fn hotkeys_from(options: &Options) -> Result<Vec<Hotkey>, String> {
+    let hotkeys = parse_hotkeys(&options.hotkeys);
+    if hotkeys.is_empty() {
+        return Err("Hotkey mode needs at least one binding, e.g. --hotkey \"save = Ctrl+S\"".to_string());
+    }
+    Ok(hotkeys)
+}
+
+// Emits a down record when a binding's combination is pressed and an up record
+// when its key or one of its modifiers is released, or the input ends
+#[derive(Debug, Default)]
+pub struct HotkeyHandler {
+    hotkeys: Vec<Hotkey>,
+    held: HeldKeys,
+    active: Vec<usize>, // Indices into `hotkeys` that are down
+}
+
+impl HotkeyHandler {
+    pub fn new(hotkeys: Vec<Hotkey>) -> Self {
+        HotkeyHandler { hotkeys, ..Self::default() }
+    }
+
+    fn hotkey_event(&self, index: usize, phase: HotkeyPhase, time: EventTime) -> OutputEvent {
+        HotkeyEvent { hotkey: self.hotkeys[index].name.clone(), phase, time }.into()
+    }
+}
+
+impl Handler for HotkeyHandler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        let time = EventTime::of(event);
+        match event.event_type {
+            EventType::KeyPress(key) => {
+                // Auto-repeat presses do not fire the hotkey again
+                if !self.held.press(key) {
+                    return;
+                }
+                let chords = Chord::pressed(self.held.keys(), key.into());
+                for index in 0..self.hotkeys.len() {
+                    if chords.contains(&self.hotkeys[index].chord) && !self.active.contains(&index) {
+                        self.active.push(index);
+                        out.push(self.hotkey_event(index, HotkeyPhase::Down, time));
+                    }
+                }
+            }
+            EventType::KeyRelease(key) => {
+                if !self.held.release(key) {
+                    return;
+                }
+                let released: Vec<usize> = self
+                    .active
+                    .iter()
+                    .copied()
+                    .filter(|index| !self.hotkeys[*index].chord.is_held(self.held.keys()))
+                    .collect();
+                self.active.retain(|index| !released.contains(index));
+                for index in released {
+                    out.push(self.hotkey_event(index, HotkeyPhase::Up, time));
+                }
+            }
+            _ => {} // Ignore other event types
+        }
+    }
+
+    // Bindings still held are released along with everything else
+    fn finish(&mut self, out: &mut Vec<OutputEvent>) {
+        let time = EventTime::now();
+        for index in mem::take(&mut self.active) {
+            out.push(self.hotkey_event(index, HotkeyPhase::Up, time));
+        }
+    }
+}
+
+pub fn hotkey_handling() {
+    hotkey_handling_with(&Options::from_env());
+}
+
+pub fn hotkey_handling_with(options: &Options) {
+    let hotkeys = match hotkeys_from(options) {
+        Ok(hotkeys) => hotkeys,
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
+
+    let sink = open_stdout(options, &[Mode::Hotkey]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(HotkeyHandler::new(hotkeys), options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for keyboard events: {}", error);
+    }
+    sink.finish();
+}
@@ -0,0 +1,152 @@
+use crate::keys::KeyName;
+use rdev::Key;
+
+// The platform keycode behind each named rdev key, mirroring rdev's own tables
+// (which it keeps private). Keys missing from a table reach us as
+// `Key::Unknown(code)` on that platform, so their code is never lost.
+
+// X11 keycodes
+#[cfg(all(unix, not(target_os = "macos")))]
+const RAW_CODES: &[(Key, u32)] = &[
+    (Key::Alt, 64), (Key::AltGr, 108), (Key::Backspace, 22), (Key::CapsLock, 66),
+    (Key::ControlLeft, 37), (Key::ControlRight, 105), (Key::Delete, 119), (Key::DownArrow, 116),
+    (Key::End, 115), (Key::Escape, 9), (Key::F1, 67), (Key::F10, 76), (Key::F11, 95),
+    (Key::F12, 96), (Key::F2, 68), (Key::F3, 69), (Key::F4, 70), (Key::F5, 71), (Key::F6, 72),
+    (Key::F7, 73), (Key::F8, 74), (Key::F9, 75), (Key::Home, 110), (Key::LeftArrow, 113),
+    (Key::MetaLeft, 133), (Key::PageDown, 117), (Key::PageUp, 112), (Key::Return, 36),
+    (Key::RightArrow, 114), (Key::ShiftLeft, 50), (Key::ShiftRight, 62), (Key::Space, 65),
+    (Key::Tab, 23), (Key::UpArrow, 111), (Key::PrintScreen, 107), (Key::ScrollLock, 78),
+    (Key::Pause, 127), (Key::NumLock, 77), (Key::BackQuote, 49), (Key::Num1, 10), (Key::Num2, 11),
+    (Key::Num3, 12), (Key::Num4, 13), (Key::Num5, 14), (Key::Num6, 15), (Key::Num7, 16),
+    (Key::Num8, 17), (Key::Num9, 18), (Key::Num0, 19), (Key::Minus, 20), (Key::Equal, 21),
+    (Key::KeyQ, 24), (Key::KeyW, 25), (Key::KeyE, 26), (Key::KeyR, 27), (Key::KeyT, 28),
+    (Key::KeyY, 29), (Key::KeyU, 30), (Key::KeyI, 31), (Key::KeyO, 32), (Key::KeyP, 33),
+    (Key::LeftBracket, 34), (Key::RightBracket, 35), (Key::KeyA, 38), (Key::KeyS, 39),
+    (Key::KeyD, 40), (Key::KeyF, 41), (Key::KeyG, 42), (Key::KeyH, 43), (Key::KeyJ, 44),
+    (Key::KeyK, 45), (Key::KeyL, 46), (Key::SemiColon, 47), (Key::Quote, 48), (Key::BackSlash, 51),
+    (Key::IntlBackslash, 94), (Key::KeyZ, 52), (Key::KeyX, 53), (Key::KeyC, 54), (Key::KeyV, 55),
+    (Key::KeyB, 56), (Key::KeyN, 57), (Key::KeyM, 58), (Key::Comma, 59), (Key::Dot, 60),
+    (Key::Slash, 61), (Key::Insert, 118), (Key::KpReturn, 104), (Key::KpMinus, 82),
+    (Key::KpPlus, 86), (Key::KpMultiply, 63), (Key::KpDivide, 106), (Key::Kp0, 90), (Key::Kp1, 87),
+    (Key::Kp2, 88), (Key::Kp3, 89), (Key::Kp4, 83), (Key::Kp5, 84), (Key::Kp6, 85), (Key::Kp7, 79),
+    (Key::Kp8, 80), (Key::Kp9, 81), (Key::KpDelete, 91),
+];
+
+// Windows virtual-key codes
+#[cfg(target_os = "windows")]
+const RAW_CODES: &[(Key, u32)] = &[
+    (Key::Alt, 164), (Key::AltGr, 165), (Key::Backspace, 8), (Key::CapsLock, 20),
+    (Key::ControlLeft, 162), (Key::ControlRight, 163), (Key::Delete, 46), (Key::DownArrow, 40),
+    (Key::End, 35), (Key::Escape, 27), (Key::F1, 112), (Key::F10, 121), (Key::F11, 122),
+    (Key::F12, 123), (Key::F2, 113), (Key::F3, 114), (Key::F4, 115), (Key::F5, 116), (Key::F6, 117),
+    (Key::F7, 118), (Key::F8, 119), (Key::F9, 120), (Key::Home, 36), (Key::LeftArrow, 37),
+    (Key::MetaLeft, 91), (Key::PageDown, 34), (Key::PageUp, 33), (Key::Return, 13),
+    (Key::RightArrow, 39), (Key::ShiftLeft, 160), (Key::ShiftRight, 161), (Key::Space, 32),
+    (Key::Tab, 9), (Key::UpArrow, 38), (Key::PrintScreen, 44), (Key::ScrollLock, 145),
+    (Key::Pause, 19), (Key::NumLock, 144), (Key::BackQuote, 192), (Key::Num1, 49), (Key::Num2, 50),
+    (Key::Num3, 51), (Key::Num4, 52), (Key::Num5, 53), (Key::Num6, 54), (Key::Num7, 55),
+    (Key::Num8, 56), (Key::Num9, 57), (Key::Num0, 48), (Key::Minus, 189), (Key::Equal, 187),
+    (Key::KeyQ, 81), (Key::KeyW, 87), (Key::KeyE, 69), (Key::KeyR, 82), (Key::KeyT, 84),
+    (Key::KeyY, 89), (Key::KeyU, 85), (Key::KeyI, 73), (Key::KeyO, 79), (Key::KeyP, 80),
+    (Key::LeftBracket, 219), (Key::RightBracket, 221), (Key::KeyA, 65), (Key::KeyS, 83),
+    (Key::KeyD, 68), (Key::KeyF, 70), (Key::KeyG, 71), (Key::KeyH, 72), (Key::KeyJ, 74),
+    (Key::KeyK, 75), (Key::KeyL, 76), (Key::SemiColon, 186), (Key::Quote, 222),
+    (Key::BackSlash, 220), (Key::IntlBackslash, 226), (Key::KeyZ, 90), (Key::KeyX, 88),
+    (Key::KeyC, 67), (Key::KeyV, 86), (Key::KeyB, 66), (Key::KeyN, 78), (Key::KeyM, 77),
+    (Key::Comma, 188), (Key::Dot, 190), (Key::Slash, 191), (Key::Insert, 45), (Key::KpMinus, 109),
+    (Key::KpPlus, 107), (Key::KpMultiply, 106), (Key::KpDivide, 111), (Key::Kp0, 96),
+    (Key::Kp1, 97), (Key::Kp2, 98), (Key::Kp3, 99), (Key::Kp4, 100), (Key::Kp5, 101),
+    (Key::Kp6, 102), (Key::Kp7, 103), (Key::Kp8, 104), (Key::Kp9, 105), (Key::KpDelete, 110),
+];
+
+// macOS virtual keycodes
+#[cfg(target_os = "macos")]
+const RAW_CODES: &[(Key, u32)] = &[
+    (Key::Alt, 58), (Key::AltGr, 61), (Key::Backspace, 51), (Key::CapsLock, 57),
+    (Key::ControlLeft, 59), (Key::ControlRight, 62), (Key::DownArrow, 125), (Key::Escape, 53),
+    (Key::F1, 122), (Key::F10, 109), (Key::F11, 103), (Key::F12, 111), (Key::F2, 120),
+    (Key::F3, 99), (Key::F4, 118), (Key::F5, 96), (Key::F6, 97), (Key::F7, 98), (Key::F8, 100),
+    (Key::F9, 101), (Key::LeftArrow, 123), (Key::MetaLeft, 55), (Key::MetaRight, 54),
+    (Key::Return, 36), (Key::RightArrow, 124), (Key::ShiftLeft, 56), (Key::ShiftRight, 60),
+    (Key::Space, 49), (Key::Tab, 48), (Key::UpArrow, 126), (Key::BackQuote, 50), (Key::Num1, 18),
+    (Key::Num2, 19), (Key::Num3, 20), (Key::Num4, 21), (Key::Num5, 23), (Key::Num6, 22),
+    (Key::Num7, 26), (Key::Num8, 28), (Key::Num9, 25), (Key::Num0, 29), (Key::Minus, 27),
+    (Key::Equal, 24), (Key::KeyQ, 12), (Key::KeyW, 13), (Key::KeyE, 14), (Key::KeyR, 15),
+    (Key::KeyT, 17), (Key::KeyY, 16), (Key::KeyU, 32), (Key::KeyI, 34), (Key::KeyO, 31),
+    (Key::KeyP, 35), (Key::LeftBracket, 33), (Key::RightBracket, 30), (Key::KeyA, 0),
+    (Key::KeyS, 1), (Key::KeyD, 2), (Key::KeyF, 3), (Key::KeyG, 5), (Key::KeyH, 4), (Key::KeyJ, 38),
+    (Key::KeyK, 40), (Key::KeyL, 37), (Key::SemiColon, 41), (Key::Quote, 39), (Key::BackSlash, 42),
+    (Key::KeyZ, 6), (Key::KeyX, 7), (Key::KeyC, 8), (Key::KeyV, 9), (Key::KeyB, 11),
+    (Key::KeyN, 45), (Key::KeyM, 46), (Key::Comma, 43), (Key::Dot, 47), (Key::Slash, 44),
+    (Key::Function, 63),
+];
+
+// Keys rdev has no name for but `KeyName` does, by the code they arrive with
+// as `Key::Unknown`
+#[cfg(all(unix, not(target_os = "macos")))]
+const EXTRA_CODES: &[(KeyName, u32)] = &[
+    (KeyName::F13, 191), (KeyName::F14, 192), (KeyName::F15, 193), (KeyName::F16, 194),
+    (KeyName::F17, 195), (KeyName::F18, 196), (KeyName::F19, 197), (KeyName::F20, 198),
+    (KeyName::VolumeMute, 121), (KeyName::VolumeDown, 122), (KeyName::VolumeUp, 123),
+    (KeyName::MediaNextTrack, 171), (KeyName::MediaPlayPause, 172), (KeyName::MediaPrevTrack, 173),
+    (KeyName::Help, 146),
+];
+
+#[cfg(target_os = "windows")]
+const EXTRA_CODES: &[(KeyName, u32)] = &[
+    (KeyName::F13, 124), (KeyName::F14, 125), (KeyName::F15, 126), (KeyName::F16, 127),
+    (KeyName::F17, 128), (KeyName::F18, 129), (KeyName::F19, 130), (KeyName::F20, 131),
+    (KeyName::VolumeMute, 173), (KeyName::VolumeDown, 174), (KeyName::VolumeUp, 175),
+    (KeyName::MediaNextTrack, 176), (KeyName::MediaPrevTrack, 177), (KeyName::MediaPlayPause, 179),
+    (KeyName::Help, 47),
+];
+
+// Media transport keys are system events on macOS and never reach the hook
+#[cfg(target_os = "macos")]
+const EXTRA_CODES: &[(KeyName, u32)] = &[
+    (KeyName::F13, 105), (KeyName::F14, 107), (KeyName::F15, 113), (KeyName::F16, 106),
+    (KeyName::F17, 64), (KeyName::F18, 79), (KeyName::F19, 80), (KeyName::F20, 90),
+    (KeyName::VolumeUp, 72), (KeyName::VolumeDown, 73), (KeyName::VolumeMute, 74),
+    (KeyName::Help, 114),
+];
+
+/// The named key behind a code rdev reported as `Key::Unknown`, if any.
+pub fn extra_key(code: u32) -> Option<KeyName> {
+    EXTRA_CODES.iter().find(|(_, table_code)| *table_code == code).map(|(key, _)| *key)
+}
+
+/// The code an extra key arrives with as `Key::Unknown`; `None` for keys that
+/// never reach the hook on this platform.
+pub fn extra_code(key: KeyName) -> Option<u32> {
+    EXTRA_CODES.iter().find(|(table_key, _)| *table_key == key).map(|(_, code)| *code)
+}
+
+/// The platform keycode the hook reported for `key`: the X11 keycode on Linux,
+/// the virtual-key code on Windows and the virtual keycode on macOS.
+pub fn raw_code(key: Key) -> Option<u32> {
+    match key {
+        Key::Unknown(code) => Some(code),
+        key => RAW_CODES.iter().find(|(table_key, _)| *table_key == key).map(|(_, code)| *code),
+    }
+}
+
+/// The layout-independent code of the physical key: the evdev code, which X11
+/// keycodes offset by 8.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn scan_code(key: Key) -> Option<u32> {
+    raw_code(key).and_then(|code| code.checked_sub(8))
+}
+
+/// The layout-independent code of the physical key. macOS virtual keycodes
+/// are already position-based.
+#[cfg(target_os = "macos")]
+pub fn scan_code(key: Key) -> Option<u32> {
+    raw_code(key)
+}
+
+/// Always `None`: virtual-key codes follow the layout and rdev drops the
+/// hook's scan code.
+#[cfg(target_os = "windows")]
+pub fn scan_code(_key: Key) -> Option<u32> {
+    None
+}
@@ -0,0 +1,222 @@
+use crate::keycodes::{extra_code, extra_key};
+use rdev::{Button, Event, Key};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+// Declares `KeyName` with one variant per rdev key plus the extra keys only the
+// simulator knows about. Variant names double as the canonical names.
+macro_rules! key_names {
+    (rdev: [$($rdev:ident),* $(,)?], extra: [$($extra:ident),* $(,)?] $(,)?) => {
+        /// A keyboard key under a stable name shared by the listener output and
+        /// the simulator input.
+        ///
+        /// Canonical names are rdev's (`KeyA`, `ShiftLeft`, `Return`, ...), so a
+        /// recorded stream can be replayed as-is. Parsing is case-insensitive and
+        /// also accepts the aliases in `ALIASES` and single US-layout characters.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum KeyName {
+            $($rdev,)*
+            $($extra,)*
+            Unknown(u32), // Platform keycode rdev has no name for
+        }
+
+        const NAMES: &[(KeyName, &str)] = &[
+            $((KeyName::$rdev, stringify!($rdev)),)*
+            $((KeyName::$extra, stringify!($extra)),)*
+        ];
+
+        impl From<Key> for KeyName {
+            fn from(key: Key) -> Self {
+                match key {
+                    $(Key::$rdev => KeyName::$rdev,)*
+                    Key::Unknown(code) => extra_key(code).unwrap_or(KeyName::Unknown(code)),
+                }
+            }
+        }
+
+        impl KeyName {
+            /// The rdev key the hook reports for this key, extra keys as their
+            /// `Key::Unknown` code; `None` for keys that never reach the hook.
+            pub fn to_rdev(self) -> Option<Key> {
+                match self {
+                    $(KeyName::$rdev => Some(Key::$rdev),)*
+                    KeyName::Unknown(code) => Some(Key::Unknown(code)),
+                    key => extra_code(key).map(Key::Unknown),
+                }
+            }
+        }
+    };
+}
+
+key_names! {
+    rdev: [
+        Alt, AltGr, Backspace, CapsLock, ControlLeft, ControlRight, Delete, DownArrow, End, Escape,
+        F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+        Home, LeftArrow, MetaLeft, MetaRight, PageDown, PageUp, Return, RightArrow,
+        ShiftLeft, ShiftRight, Space, Tab, UpArrow, PrintScreen, ScrollLock, Pause, NumLock,
+        BackQuote, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9, Num0, Minus, Equal,
+        KeyQ, KeyW, KeyE, KeyR, KeyT, KeyY, KeyU, KeyI, KeyO, KeyP, LeftBracket, RightBracket,
+        KeyA, KeyS, KeyD, KeyF, KeyG, KeyH, KeyJ, KeyK, KeyL, SemiColon, Quote, BackSlash, IntlBackslash,
+        KeyZ, KeyX, KeyC, KeyV, KeyB, KeyN, KeyM, Comma, Dot, Slash,
+        Insert, KpReturn, KpMinus, KpPlus, KpMultiply, KpDivide,
+        Kp0, Kp1, Kp2, Kp3, Kp4, Kp5, Kp6, Kp7, Kp8, Kp9, KpDelete, Function,
+    ],
+    extra: [
+        F13, F14, F15, F16, F17, F18, F19, F20,
+        VolumeUp, VolumeDown, VolumeMute, MediaPlayPause, MediaNextTrack, MediaPrevTrack, Help,
+    ],
+}
+
+// Other accepted spellings, mostly the names the simulator used before the
+// listener and simulator shared this table
+const ALIASES: &[(&str, KeyName)] = &[
+    ("Shift", KeyName::ShiftLeft),
+    ("Control", KeyName::ControlLeft),
+    ("Ctrl", KeyName::ControlLeft),
+    ("Meta", KeyName::MetaLeft),
+    ("Super", KeyName::MetaLeft),
+    ("Command", KeyName::MetaLeft),
+    ("Cmd", KeyName::MetaLeft),
+    ("Option", KeyName::Alt),
+    ("Enter", KeyName::Return),
+    ("Esc", KeyName::Escape),
+    ("PrintScr", KeyName::PrintScreen),
+    ("Fn", KeyName::Function),
+    ("Numpad0", KeyName::Kp0),
+    ("Numpad1", KeyName::Kp1),
+    ("Numpad2", KeyName::Kp2),
+    ("Numpad3", KeyName::Kp3),
+    ("Numpad4", KeyName::Kp4),
+    ("Numpad5", KeyName::Kp5),
+    ("Numpad6", KeyName::Kp6),
+    ("Numpad7", KeyName::Kp7),
+    ("Numpad8", KeyName::Kp8),
+    ("Numpad9", KeyName::Kp9),
+];
+
+// Keys that produce a character on a US layout. Main-block keys come first so
+// `from_char` prefers them over the keypad.
+const CHARACTERS: &[(KeyName, char)] = &[
+    (KeyName::KeyA, 'a'), (KeyName::KeyB, 'b'), (KeyName::KeyC, 'c'), (KeyName::KeyD, 'd'),
+    (KeyName::KeyE, 'e'), (KeyName::KeyF, 'f'), (KeyName::KeyG, 'g'), (KeyName::KeyH, 'h'),
+    (KeyName::KeyI, 'i'), (KeyName::KeyJ, 'j'), (KeyName::KeyK, 'k'), (KeyName::KeyL, 'l'),
+    (KeyName::KeyM, 'm'), (KeyName::KeyN, 'n'), (KeyName::KeyO, 'o'), (KeyName::KeyP, 'p'),
+    (KeyName::KeyQ, 'q'), (KeyName::KeyR, 'r'), (KeyName::KeyS, 's'), (KeyName::KeyT, 't'),
+    (KeyName::KeyU, 'u'), (KeyName::KeyV, 'v'), (KeyName::KeyW, 'w'), (KeyName::KeyX, 'x'),
+    (KeyName::KeyY, 'y'), (KeyName::KeyZ, 'z'),
+    (KeyName::Num0, '0'), (KeyName::Num1, '1'), (KeyName::Num2, '2'), (KeyName::Num3, '3'),
+    (KeyName::Num4, '4'), (KeyName::Num5, '5'), (KeyName::Num6, '6'), (KeyName::Num7, '7'),
+    (KeyName::Num8, '8'), (KeyName::Num9, '9'),
+    (KeyName::BackQuote, '`'), (KeyName::Minus, '-'), (KeyName::Equal, '='),
+    (KeyName::LeftBracket, '['), (KeyName::RightBracket, ']'), (KeyName::SemiColon, ';'),
+    (KeyName::Quote, '\''), (KeyName::BackSlash, '\\'), (KeyName::Comma, ','),
+    (KeyName::Dot, '.'), (KeyName::Slash, '/'), (KeyName::Space, ' '),
+    (KeyName::Kp0, '0'), (KeyName::Kp1, '1'), (KeyName::Kp2, '2'), (KeyName::Kp3, '3'),
+    (KeyName::Kp4, '4'), (KeyName::Kp5, '5'), (KeyName::Kp6, '6'), (KeyName::Kp7, '7'),
+    (KeyName::Kp8, '8'), (KeyName::Kp9, '9'), (KeyName::KpMinus, '-'), (KeyName::KpPlus, '+'),
+    (KeyName::KpMultiply, '*'), (KeyName::KpDivide, '/'), (KeyName::KpDelete, '.'),
+];
+
+impl KeyName {
+    /// Every named key, in canonical order.
+    pub fn all() -> impl Iterator<Item = KeyName> {
+        NAMES.iter().map(|(key, _)| *key)
+    }
+
+    /// The character this key types on a US layout without modifiers.
+    pub fn to_char(self) -> Option<char> {
+        CHARACTERS.iter().find(|(key, _)| *key == self).map(|(_, c)| *c)
+    }
+
+    /// The key that types `c` on a US layout without modifiers, from the main
+    /// block where it has one: `+` and `*` only come from the keypad.
+    pub fn from_char(c: char) -> Option<Self> {
+        let c = c.to_ascii_lowercase();
+        CHARACTERS.iter().find(|(_, key_char)| *key_char == c).map(|(key, _)| *key)
+    }
+}
+
+impl fmt::Display for KeyName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyName::Unknown(code) => write!(f, "Unknown({})", code),
+            key => {
+                let (_, name) = NAMES.iter().find(|(name_key, _)| name_key == key).expect("every named key is in NAMES");
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+impl FromStr for KeyName {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let prefix = "Unknown(";
+        if let Some(code) = s.get(..prefix.len()).filter(|start| start.eq_ignore_ascii_case(prefix)).and_then(|_| s[prefix.len()..].strip_suffix(')')) {
+            return code.parse().map(KeyName::Unknown).map_err(|_| format!("Invalid key code: {}", s));
+        }
+
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return KeyName::from_char(c).ok_or_else(|| format!("Unknown key: {}", s));
+        }
+
+        NAMES.iter()
+            .map(|(key, name)| (*name, *key))
+            .chain(ALIASES.iter().copied())
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|(_, key)| key)
+            .ok_or_else(|| format!("Unknown key: {}", s))
+    }
+}
+
+impl Serialize for KeyName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(de::Error::custom)
+    }
+}
+
+// Canonical names only; aliases are accepted on input but never emitted
+impl JsonSchema for KeyName {
+    fn schema_name() -> Cow<'static, str> {
+        "KeyName".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let names: Vec<&str> = NAMES.iter().map(|(_, name)| *name).collect();
+        json_schema!({
+            "anyOf": [
+                { "type": "string", "enum": names },
+                { "type": "string", "pattern": "^Unknown\\([0-9]+\\)$" }
+            ]
+        })
+    }
+}
+
+/// The text a key event produced under the active keyboard layout, as resolved
+/// by rdev. `None` for releases, dead keys and keys that type nothing.
+pub fn event_text(event: &Event) -> Option<String> {
+    event.name.clone().filter(|name| !name.is_empty())
+}
+
+/// Name of a mouse button as written in `mouse_button` events.
+pub fn button_name(button: Button) -> String {
+    match button {
+        Button::Left => "left".to_string(),
+        Button::Right => "right".to_string(),
+        Button::Middle => "middle".to_string(),
+        Button::Unknown(code) => format!("button_{}", code), // Handle unknown buttons
+    }
+}
@@ -1,5 +1,22 @@
+pub mod keys;
+pub mod keycodes;
+pub mod modifiers;
+pub mod clock;
+pub mod format;
+pub mod filter;
 pub mod models;
+pub mod source;
+pub mod sink;
+pub mod handler;
+pub mod hello;
+pub mod listener;
+pub mod mode;
+pub mod options;
+pub mod output;
+pub mod schema;
 
+#[cfg(feature = "simulation")]
+pub mod backend;
 #[cfg(feature = "simulation")]
 pub mod simulation;
 #[cfg(feature = "direct")]
@@ -10,3 +27,17 @@ pub mod complex;
 pub mod hold_and_release;
 #[cfg(feature = "mouse")]
 pub mod mouse;
+#[cfg(feature = "combined")]
+pub mod combined;
+#[cfg(feature = "duplex")]
+pub mod duplex;
+#[cfg(feature = "hotkey")]
+pub mod hotkey;
+#[cfg(feature = "sequence")]
+pub mod sequence;
+#[cfg(feature = "gesture")]
+pub mod gesture;
+#[cfg(feature = "text")]
+pub mod text;
+#[cfg(feature = "stats")]
+pub mod stats;
@@ -0,0 +1,213 @@
+use rdev::Event;
+use crate::clock;
+use crate::filter::{EventFilter, Filtered};
+use crate::handler::{drive, Handler};
+use crate::models::{OutputEvent, Record};
+use crate::sink::EventSink;
+use crate::source::{EventSource, RdevSource, SourceError};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+#[cfg(feature = "direct")]
+use crate::direct::DirectHandler;
+#[cfg(feature = "mouse")]
+use crate::mouse::MouseHandler;
+
+// How often the worker checks for a stop request while no events arrive
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+enum Message {
+    Event(Event),
+    Done(Result<(), SourceError>),
+}
+
+type Callback = Box<dyn FnMut(OutputEvent) + Send>;
+
+/// Builder for embedding the listener in a Rust application.
+///
+/// ```no_run
+/// use key_listener::listener::Listener;
+///
+/// let handle = Listener::new()
+///     .keyboard(true)
+///     .mouse(true)
+///     .on_event(|event| println!("{:?}", event))
+///     .start();
+/// // ...
+/// handle.stop();
+/// ```
+pub struct Listener {
+    keyboard: bool,
+    mouse: bool,
+    handlers: Vec<Box<dyn Handler + Send>>,
+    filter: EventFilter,
+    source: Box<dyn EventSource + Send>,
+    callback: Callback,
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Listener {
+            keyboard: true,
+            mouse: false,
+            handlers: Vec::new(),
+            filter: EventFilter::default(),
+            source: Box::new(RdevSource),
+            callback: Box::new(|_| {}),
+        }
+    }
+}
+
+impl Listener {
+    /// Listens to the keyboard through the OS hook by default.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emit a `KeyEvent` for every key press and release.
+    pub fn keyboard(mut self, enabled: bool) -> Self {
+        self.keyboard = enabled;
+        self
+    }
+
+    /// Emit a mouse move, button or scroll event for every pointer event.
+    pub fn mouse(mut self, enabled: bool) -> Self {
+        self.mouse = enabled;
+        self
+    }
+
+    /// Run an additional mode, e.g. `ComplexHandler`, on the same events.
+    pub fn handler(mut self, handler: impl Handler + Send + 'static) -> Self {
+        self.handlers.push(Box::new(handler));
+        self
+    }
+
+    /// Feed every mode only the keys and buttons `filter` allows.
+    pub fn filter(mut self, filter: EventFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Read events from `source` instead of the OS hook.
+    pub fn source(mut self, source: impl EventSource + Send + 'static) -> Self {
+        self.source = Box::new(source);
+        self
+    }
+
+    pub fn on_event(mut self, callback: impl FnMut(OutputEvent) + Send + 'static) -> Self {
+        self.callback = Box::new(callback);
+        self
+    }
+
+    fn enabled_handlers(&mut self) -> Vec<Box<dyn Handler + Send>> {
+        let mut handlers: Vec<Box<dyn Handler + Send>> = Vec::new();
+        if self.keyboard {
+            #[cfg(feature = "direct")]
+            handlers.push(Box::new(DirectHandler::default()));
+            #[cfg(not(feature = "direct"))]
+            eprintln!("Keyboard events require the `direct` feature");
+        }
+        if self.mouse {
+            #[cfg(feature = "mouse")]
+            handlers.push(Box::new(MouseHandler::default()));
+            #[cfg(not(feature = "mouse"))]
+            eprintln!("Mouse events require the `mouse` feature");
+        }
+        handlers.append(&mut self.handlers);
+        handlers
+    }
+
+    /// Starts listening on a background thread.
+    pub fn start(mut self) -> ListenerHandle {
+        clock::start();
+        let filter = self.filter.clone();
+        let handlers: Vec<(Option<String>, Box<dyn Handler + Send>)> = self
+            .enabled_handlers()
+            .into_iter()
+            .map(|handler| (None, Box::new(Filtered::new(handler, filter.clone())) as Box<dyn Handler + Send>))
+            .collect();
+        let mut source = self.source;
+        let callback = self.callback;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let mut forwarded = Forwarded { receiver, stopped: Arc::clone(&stopped), live: source.is_live() };
+
+        // The source runs on its own thread: the OS hook never returns, so the
+        // worker below must be able to stop without waiting for it.
+        let event_sender = sender.clone();
+        thread::spawn(move || {
+            let result = source.listen(Box::new(move |event: Event| {
+                let _ = event_sender.send(Message::Event(event));
+            }));
+            let _ = sender.send(Message::Done(result));
+        });
+
+        let worker = thread::spawn(move || drive(&mut forwarded, handlers, CallbackSink(callback)));
+
+        ListenerHandle { stopped, worker }
+    }
+}
+
+// The events of the source thread, until it is done or the listener is stopped
+struct Forwarded {
+    receiver: Receiver<Message>,
+    stopped: Arc<AtomicBool>,
+    live: bool,
+}
+
+impl EventSource for Forwarded {
+    fn listen(&mut self, mut callback: Box<dyn FnMut(Event)>) -> Result<(), SourceError> {
+        while !self.stopped.load(Ordering::SeqCst) {
+            match self.receiver.recv_timeout(STOP_POLL_INTERVAL) {
+                Ok(Message::Event(event)) => callback(event),
+                Ok(Message::Done(result)) => return result,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn is_live(&self) -> bool {
+        self.live
+    }
+}
+
+// Hands every output event to the listener's callback
+struct CallbackSink(Callback);
+
+impl EventSink for CallbackSink {
+    fn emit(&mut self, record: Record) -> io::Result<()> {
+        (self.0)(record.event);
+        Ok(())
+    }
+}
+
+/// Handle to a running `Listener`.
+pub struct ListenerHandle {
+    stopped: Arc<AtomicBool>,
+    worker: JoinHandle<Result<(), SourceError>>,
+}
+
+impl ListenerHandle {
+    /// Stops delivering events, after handing the modes' final output (such as
+    /// closing statistics) to the callback. The OS hook itself stays installed
+    /// until the process exits, since `rdev` offers no way to remove it.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+
+    /// Waits until the listener stops, either through `stop` or because the
+    /// source ran out of events or failed to start.
+    pub fn join(self) -> Result<(), SourceError> {
+        self.worker.join().expect("listener worker panicked")
+    }
+}
@@ -1,5 +1,22 @@
+pub mod keys;
+pub mod keycodes;
+pub mod modifiers;
+pub mod clock;
+pub mod format;
+pub mod filter;
 pub mod models;
+pub mod source;
+pub mod sink;
+pub mod handler;
+pub mod hello;
+pub mod listener;
+pub mod mode;
+pub mod options;
+pub mod output;
+pub mod schema;
 
+#[cfg(feature = "simulation")]
+pub mod backend;
 #[cfg(feature = "simulation")]
 pub mod simulation;
 #[cfg(feature = "direct")]
@@ -10,76 +27,69 @@ pub mod complex;
 pub mod hold_and_release;
 #[cfg(feature = "mouse")]
 pub mod mouse;
+#[cfg(feature = "combined")]
+pub mod combined;
+#[cfg(feature = "duplex")]
+pub mod duplex;
+#[cfg(feature = "hotkey")]
+pub mod hotkey;
+#[cfg(feature = "sequence")]
+pub mod sequence;
+#[cfg(feature = "gesture")]
+pub mod gesture;
+#[cfg(feature = "text")]
+pub mod text;
+#[cfg(feature = "stats")]
+pub mod stats;
 
-#[cfg(feature = "simulation")]
-use crate::simulation::*;
-#[cfg(feature = "direct")]
-use crate::direct::*;
-#[cfg(feature = "complex")]
-use crate::complex::*;
-#[cfg(feature = "hold_and_release")]
-use crate::hold_and_release::*;
-#[cfg(feature = "mouse")]
-use crate::mouse::*;
+use crate::filter::Filtered;
+use crate::handler::{run_modes, Handler};
+use crate::mode::{parse_modes, Mode};
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::schema::wire_schema;
+use crate::source::RdevSource;
 
 use std::env;
+use std::process;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    
-    let handling_type = if args.len() > 1 {
-        match args[1].to_uppercase().as_str() {
-            #[cfg(feature = "direct")]
-            "DIRECT" => 1,
-            #[cfg(feature = "complex")]
-            "COMPLEX" => 2,
-            #[cfg(feature = "hold_and_release")]
-            "HOLD_AND_RELEASE" => 3,
-            #[cfg(feature = "simulation")]
-            "SIMULATION" => 4,
-            #[cfg(feature = "mouse")]
-            "MOUSE" => 5,
-            _ => {
-                #[cfg(feature = "direct")]
-                { 1 }
-                #[cfg(all(not(feature = "direct"), feature = "complex"))]
-                { 2 }
-                #[cfg(all(not(feature = "direct"), not(feature = "complex"), feature = "hold_and_release"))]
-                { 3 }
-                #[cfg(all(not(feature = "direct"), not(feature = "complex"), not(feature = "hold_and_release"), feature = "simulation"))]
-                { 4 }
-                #[cfg(all(not(feature = "direct"), not(feature = "complex"), not(feature = "hold_and_release"), not(feature = "simulation"), feature = "mouse"))]
-                { 5 }
-                #[cfg(not(any(feature = "direct", feature = "complex", feature = "hold_and_release", feature = "simulation", feature = "mouse")))]
-                { panic!("No features enabled!") }
+    let (options, args) = Options::parse(env::args().skip(1));
+
+    // `schema` prints the JSON Schema of every wire message instead of running a mode
+    if args.first().is_some_and(|arg| arg.eq_ignore_ascii_case("schema")) {
+        println!("{}", serde_json::to_string_pretty(&wire_schema()).unwrap());
+        return;
+    }
+
+    // Accepts a single mode or a comma-separated list such as `direct,hold_and_release,mouse`
+    let mut modes = args.first().map(|arg| parse_modes(arg)).unwrap_or_default();
+    if modes.is_empty() {
+        modes.push(Mode::default());
+    }
+
+    if let [mode] = modes.as_slice() {
+        mode.run(&options);
+        return;
+    }
+
+    let mut handlers = Vec::new();
+    for mode in &modes {
+        match mode.handler(&options) {
+            Ok(handler) => {
+                let handler: Box<dyn Handler + Send> = Box::new(Filtered::new(handler, options.filter.clone()));
+                handlers.push((mode.name().to_string(), handler));
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
             }
         }
-    } else {
-        #[cfg(feature = "direct")]
-        { 1 }
-        #[cfg(all(not(feature = "direct"), feature = "complex"))]
-        { 2 }
-        #[cfg(all(not(feature = "direct"), not(feature = "complex"), feature = "hold_and_release"))]
-        { 3 }
-        #[cfg(all(not(feature = "direct"), not(feature = "complex"), not(feature = "hold_and_release"), feature = "simulation"))]
-        { 4 }
-        #[cfg(all(not(feature = "direct"), not(feature = "complex"), not(feature = "hold_and_release"), not(feature = "simulation"), feature = "mouse"))]
-        { 5 }
-        #[cfg(not(any(feature = "direct", feature = "complex", feature = "hold_and_release", feature = "simulation", feature = "mouse")))]
-        { panic!("No features enabled!") }
-    };
-    
-    match handling_type {
-        #[cfg(feature = "direct")]
-        1 => direct_handling(),
-        #[cfg(feature = "complex")]
-        2 => complex_handling(),
-        #[cfg(feature = "hold_and_release")]
-        3 => hold_and_release_handling(),
-        #[cfg(feature = "simulation")]
-        4 => key_simulation_handling(),
-        #[cfg(feature = "mouse")]
-        5 => mouse_handling(),
-        _ => panic!("Invalid handling type"),
     }
-}
\ No newline at end of file
+
+    let sink = open_stdout(&options, &modes);
+    if let Err(error) = run_modes(&mut RdevSource, handlers, sink.clone()) {
+        eprintln!("Error listening for input events: {}", error);
+    }
+    sink.finish();
+}
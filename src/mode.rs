@@ -0,0 +1,218 @@
+use crate::handler::Handler;
+use crate::options::Options;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "simulation")]
+use crate::simulation::key_simulation_handling_with;
+#[cfg(feature = "direct")]
+use crate::direct::{direct_handling_with, DirectHandler};
+#[cfg(feature = "complex")]
+use crate::complex::{complex_handling_with, ComplexHandler};
+#[cfg(feature = "hold_and_release")]
+use crate::hold_and_release::{hold_and_release_handling_with, HoldAndReleaseHandler};
+#[cfg(feature = "mouse")]
+use crate::mouse::{mouse_handling_with, MouseHandler};
+#[cfg(feature = "combined")]
+use crate::combined::{combined_handling_with, CombinedHandler};
+#[cfg(feature = "duplex")]
+use crate::duplex::duplex_handling_with;
+#[cfg(feature = "hotkey")]
+use crate::hotkey::{hotkey_handling_with, hotkeys_from, HotkeyHandler};
+#[cfg(feature = "sequence")]
+use crate::sequence::{sequence_handling_with, sequences_from, SequenceHandler, DEFAULT_STEP_TIMEOUT};
+#[cfg(feature = "gesture")]
+use crate::gesture::{gesture_handling_with, GestureHandler, GestureThresholds};
+#[cfg(feature = "text")]
+use crate::text::{text_handling_with, TextHandler, DEFAULT_IDLE_TIMEOUT};
+#[cfg(feature = "stats")]
+use crate::stats::{stats_handling_with, stats_interval, StatsHandler};
+
+// The modes compiled into this build. The default is the first one available
+// in the order below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[cfg(feature = "direct")]
+    #[default]
+    Direct,
+    #[cfg(feature = "complex")]
+    #[cfg_attr(not(feature = "direct"), default)]
+    Complex,
+    #[cfg(feature = "hold_and_release")]
+    #[cfg_attr(all(not(feature = "direct"), not(feature = "complex")), default)]
+    HoldAndRelease,
+    #[cfg(feature = "simulation")]
+    #[cfg_attr(all(not(feature = "direct"), not(feature = "complex"), not(feature = "hold_and_release")), default)]
+    Simulation,
+    #[cfg(feature = "mouse")]
+    #[cfg_attr(all(not(feature = "direct"), not(feature = "complex"), not(feature = "hold_and_release"), not(feature = "simulation")), default)]
+    Mouse,
+    #[cfg(feature = "combined")]
+    Combined,
+    #[cfg(feature = "duplex")]
+    Duplex,
+    #[cfg(feature = "hotkey")]
+    Hotkey,
+    #[cfg(feature = "sequence")]
+    Sequence,
+    #[cfg(feature = "gesture")]
+    #[cfg_attr(all(not(feature = "direct"), not(feature = "complex"), not(feature = "hold_and_release"), not(feature = "simulation"), not(feature = "mouse")), default)]
+    Gesture,
+    #[cfg(feature = "text")]
+    #[cfg_attr(all(not(feature = "direct"), not(feature = "complex"), not(feature = "hold_and_release"), not(feature = "simulation"), not(feature = "mouse"), not(feature = "gesture")), default)]
+    Text,
+    #[cfg(feature = "stats")]
+    #[cfg_attr(all(not(feature = "direct"), not(feature = "complex"), not(feature = "hold_and_release"), not(feature = "simulation"), not(feature = "mouse"), not(feature = "gesture"), not(feature = "text")), default)]
+    Stats,
+}
+
+impl Mode {
+    // Name used on the command line and in the `mode` field of tagged records
+    pub fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "direct")]
+            Mode::Direct => "direct",
+            #[cfg(feature = "complex")]
+            Mode::Complex => "complex",
+            #[cfg(feature = "hold_and_release")]
+            Mode::HoldAndRelease => "hold_and_release",
+            #[cfg(feature = "simulation")]
+            Mode::Simulation => "simulation",
+            #[cfg(feature = "mouse")]
+            Mode::Mouse => "mouse",
+            #[cfg(feature = "combined")]
+            Mode::Combined => "all",
+            #[cfg(feature = "duplex")]
+            Mode::Duplex => "duplex",
+            #[cfg(feature = "hotkey")]
+            Mode::Hotkey => "hotkey",
+            #[cfg(feature = "sequence")]
+            Mode::Sequence => "sequence",
+            #[cfg(feature = "gesture")]
+            Mode::Gesture => "gesture",
+            #[cfg(feature = "text")]
+            Mode::Text => "text",
+            #[cfg(feature = "stats")]
+            Mode::Stats => "stats",
+        }
+    }
+
+    // A fresh state machine for listener modes, or why this mode cannot run
+    // alongside others: it reads stdin, or `options` leave it nothing to do
+    #[cfg_attr(not(any(feature = "direct", feature = "hotkey", feature = "gesture", feature = "text", feature = "stats")), allow(unused_variables))]
+    pub fn handler(&self, options: &Options) -> Result<Box<dyn Handler + Send>, String> {
+        match self {
+            #[cfg(feature = "direct")]
+            Mode::Direct => Ok(Box::new(DirectHandler::new(options.suppress_repeats))),
+            #[cfg(feature = "complex")]
+            Mode::Complex => Ok(Box::new(ComplexHandler::default())),
+            #[cfg(feature = "hold_and_release")]
+            Mode::HoldAndRelease => Ok(Box::new(HoldAndReleaseHandler::new(options.timeline))),
+            #[cfg(feature = "simulation")]
+            Mode::Simulation => Err(format!("{} mode cannot be combined with other modes", self)),
+            #[cfg(feature = "mouse")]
+            Mode::Mouse => Ok(Box::new(MouseHandler::default())),
+            #[cfg(feature = "combined")]
+            Mode::Combined => Ok(Box::new(CombinedHandler::default())),
+            #[cfg(feature = "duplex")]
+            Mode::Duplex => Err(format!("{} mode cannot be combined with other modes", self)),
+            #[cfg(feature = "hotkey")]
+            Mode::Hotkey => Ok(Box::new(HotkeyHandler::new(hotkeys_from(options)?))),
+            #[cfg(feature = "sequence")]
+            Mode::Sequence => Ok(Box::new(SequenceHandler::new(
+                sequences_from(options)?,
+                options.sequence_timeout.unwrap_or(DEFAULT_STEP_TIMEOUT),
+            ))),
+            #[cfg(feature = "gesture")]
+            Mode::Gesture => Ok(Box::new(GestureHandler::new(GestureThresholds::from_options(options)))),
+            #[cfg(feature = "text")]
+            Mode::Text => Ok(Box::new(TextHandler::new(options.text_idle.unwrap_or(DEFAULT_IDLE_TIMEOUT)))),
+            #[cfg(feature = "stats")]
+            Mode::Stats => Ok(Box::new(StatsHandler::new(stats_interval(options)))),
+        }
+    }
+
+    // Runs this mode on its own, blocking the thread
+    pub fn run(&self, options: &Options) {
+        match self {
+            #[cfg(feature = "direct")]
+            Mode::Direct => direct_handling_with(options),
+            #[cfg(feature = "complex")]
+            Mode::Complex => complex_handling_with(options),
+            #[cfg(feature = "hold_and_release")]
+            Mode::HoldAndRelease => hold_and_release_handling_with(options),
+            #[cfg(feature = "simulation")]
+            Mode::Simulation => key_simulation_handling_with(options),
+            #[cfg(feature = "mouse")]
+            Mode::Mouse => mouse_handling_with(options),
+            #[cfg(feature = "combined")]
+            Mode::Combined => combined_handling_with(options),
+            #[cfg(feature = "duplex")]
+            Mode::Duplex => duplex_handling_with(options),
+            #[cfg(feature = "hotkey")]
+            Mode::Hotkey => hotkey_handling_with(options),
+            #[cfg(feature = "sequence")]
+            Mode::Sequence => sequence_handling_with(options),
+            #[cfg(feature = "gesture")]
+            Mode::Gesture => gesture_handling_with(options),
+            #[cfg(feature = "text")]
+            Mode::Text => text_handling_with(options),
+            #[cfg(feature = "stats")]
+            Mode::Stats => stats_handling_with(options),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            #[cfg(feature = "direct")]
+            "DIRECT" => Ok(Mode::Direct),
+            #[cfg(feature = "complex")]
+            "COMPLEX" => Ok(Mode::Complex),
+            #[cfg(feature = "hold_and_release")]
+            "HOLD_AND_RELEASE" => Ok(Mode::HoldAndRelease),
+            #[cfg(feature = "simulation")]
+            "SIMULATION" => Ok(Mode::Simulation),
+            #[cfg(feature = "mouse")]
+            "MOUSE" => Ok(Mode::Mouse),
+            #[cfg(feature = "combined")]
+            "ALL" | "COMBINED" => Ok(Mode::Combined),
+            #[cfg(feature = "duplex")]
+            "DUPLEX" => Ok(Mode::Duplex),
+            #[cfg(feature = "hotkey")]
+            "HOTKEY" | "HOTKEYS" => Ok(Mode::Hotkey),
+            #[cfg(feature = "sequence")]
+            "SEQUENCE" | "SEQUENCES" => Ok(Mode::Sequence),
+            #[cfg(feature = "gesture")]
+            "GESTURE" | "GESTURES" => Ok(Mode::Gesture),
+            #[cfg(feature = "text")]
+            "TEXT" => Ok(Mode::Text),
+            #[cfg(feature = "stats")]
+            "STATS" | "STATISTICS" => Ok(Mode::Stats),
+            _ => Err(format!("Unknown mode: {}", s)),
+        }
+    }
+}
+
+// Parses a comma-separated mode list such as `direct,hold_and_release,mouse`.
+// Unknown names are reported and skipped; duplicates are dropped.
+pub fn parse_modes(arg: &str) -> Vec<Mode> {
+    let mut modes = Vec::new();
+    for name in arg.split(',').filter(|name| !name.trim().is_empty()) {
+        match name.parse::<Mode>() {
+            Ok(mode) if !modes.contains(&mode) => modes.push(mode),
+            Ok(_) => {}
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+    modes
+}
@@ -1,37 +1,236 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use crate::clock::{EventTime, Timestamp};
+use crate::keys::KeyName;
+use std::collections::BTreeMap;
 
-#[derive(Serialize)]
+/// A single key press or release.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct KeyEvent {
-    pub key: String,
-    pub event_type: String,
+    pub key: KeyName,
     pub pressed: bool,
-    pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>, // Text the key produced under the active layout, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_code: Option<u32>, // Layout-independent physical key code, where the platform provides one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_code: Option<u32>, // Platform keycode (X11 keycode, Windows virtual key, macOS keycode)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub repeat: bool, // An OS auto-repeat press of a key that is already held (direct mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_count: Option<u32>, // Auto-repeats so far in the current hold, on repeats and on the release ending it (direct mode)
+    #[serde(default)]
+    pub modifiers: Modifiers,
+    #[serde(flatten)]
+    pub time: EventTime,
+}
+
+/// Which modifier keys were held and which lock keys were on, after the
+/// event carrying it. Only the `true` ones are written.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shift: bool, // Either Shift key is held
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shift_left: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shift_right: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ctrl: bool, // Either Control key is held
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ctrl_left: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ctrl_right: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub alt: bool, // Either Alt (Option) key is held
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub alt_left: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub alt_right: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub alt_gr: bool, // The right Alt key, as AltGr on layouts that type with it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub meta: bool, // Either Meta (Windows, Command) key is held
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub meta_left: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub meta_right: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub caps_lock: bool, // Toggled on
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub num_lock: bool, // Toggled on
 }
 
-#[derive(Serialize)]
+/// The keys currently held down together, in the order they were pressed.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct ComplexKeyEvent {
-    pub keys: Vec<String>,
-    pub event_type: String,
-    pub timestamp: u64,
+    pub keys: Vec<KeyName>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>, // Text produced by the key that triggered this update
+    #[serde(flatten)]
+    pub time: EventTime,
 }
 
-#[derive(Serialize)]
+/// One key going down or up inside a hold_and_release sequence.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct KeyTransition {
+    pub key: KeyName,
+    pub pressed: bool,
+    pub offset_us: u64, // Since the sequence's first press, on the monotonic clock
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hold_us: Option<u64>, // On releases: how long this press of the key lasted
+}
+
+/// Every key pressed between the first press and the last release.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct KeySequenceEvent {
-    pub keys: Vec<String>,
-    pub start_time: u64,
-    pub end_time: u64,
+    pub keys: Vec<KeyName>,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
     pub duration_ms: u64,
-    pub event_type: String
+    pub duration_us: u64, // Measured on the monotonic clock
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeline: Option<Vec<KeyTransition>>, // Every press and release in order, with --timeline
+}
+
+/// Whether a hotkey went down or came back up.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyPhase {
+    Down,
+    Up,
+}
+
+/// A configured hotkey was pressed or released.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct HotkeyEvent {
+    pub hotkey: String, // Binding name
+    pub phase: HotkeyPhase,
+    #[serde(flatten)]
+    pub time: EventTime,
+}
+
+/// How a multi-step key sequence ended.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceOutcome {
+    /// The steps completed a binding.
+    Match,
+    /// The next step did not come in time.
+    Timeout,
+    /// A key that continues no binding broke the sequence off.
+    Abort,
+}
+
+/// A multi-step key sequence matched a binding, timed out or was broken off.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct SequenceEvent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<String>, // Binding name, for matches
+    pub outcome: SequenceOutcome,
+    pub steps: Vec<String>, // Chords typed, e.g. ["Ctrl+KeyK", "Ctrl+KeyC"]
+    #[serde(flatten)]
+    pub time: EventTime,
+}
+
+/// How a key was used.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GestureKind {
+    /// Pressed and released quickly, once.
+    Tap,
+    /// Tapped twice in a row.
+    DoubleTap,
+    /// Tapped three or more times in a row; `count` says how often.
+    MultiTap,
+    /// Held longer than a tap but released before a long press.
+    Hold,
+    /// Held past the long-press threshold; reported while the key is still down.
+    LongPress,
+}
+
+/// A key's activity classified as taps, a hold or a long press.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct GestureEvent {
+    pub key: KeyName,
+    pub gesture: GestureKind,
+    pub count: u32, // Taps in a row; 1 for holds and long presses
+    pub duration_ms: u64, // From the first press to the last release, or the threshold a long press passed
+    #[serde(flatten)]
+    pub time: EventTime,
+}
+
+/// Why text mode committed its buffer.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitReason {
+    /// Return or keypad Enter was pressed.
+    Return,
+    /// Tab was pressed, usually moving focus to the next field.
+    Tab,
+    /// A mouse button was pressed, which may move the caret or the focus.
+    Click,
+    /// A key was pressed with Ctrl, Alt or Meta held, such as Alt+Tab or Ctrl+V.
+    Shortcut,
+    /// The caret left the buffer: Up, Down, Page Up, Page Down, Escape, Left
+    /// past its start, or a Shift selection.
+    Navigation,
+    /// No key was typed for the idle timeout.
+    Idle,
+    /// The input ended, e.g. on Ctrl+C.
+    End,
 }
 
-#[derive(Deserialize)]
+/// Text typed since the last commit, after Backspace, Delete and cursor edits.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct TextCommitEvent {
+    pub text: String,
+    pub reason: CommitReason,
+    #[serde(flatten)]
+    pub time: EventTime,
+}
+
+/// Presses and hold time of one key.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+pub struct KeyStats {
+    pub presses: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_hold_ms: Option<f64>, // Over the presses released so far
+}
+
+/// How many intervals between consecutive key presses fell into one range.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct IntervalBucket {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub below_ms: Option<u64>, // Exclusive upper bound; absent for the last, open-ended bucket
+    pub count: u64,
+}
+
+/// Typing statistics since the mode started.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct StatsEvent {
+    pub elapsed_ms: u64, // Since the mode started
+    pub active_ms: u64, // Time spent typing: intervals between presses, leaving out pauses
+    pub presses: u64, // Key presses, not counting auto-repeat
+    pub keys_per_minute: f64, // Presses per minute of active time
+    pub words_per_minute: f64, // Presses that typed text, five to a word, per minute of active time
+    pub backspace_ratio: f64, // Backspace presses per key press
+    pub keys: BTreeMap<String, KeyStats>, // By key name
+    pub intervals: Vec<IntervalBucket>, // Histogram of the time between consecutive presses
+    #[serde(rename = "final")]
+    pub is_final: bool, // Last statistics before the stream ends
+    #[serde(flatten)]
+    pub time: EventTime,
+}
+
+#[derive(Deserialize, JsonSchema)]
 pub struct KeySimulationEvent {
     pub key: String,
     pub action: String, // "press", "release", or "tap"
     pub delay_after_ms: Option<u64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct MouseSimulationEvent {
     pub action: String, // "move", "click", "press", "release", "scroll"
     pub x: Option<i32>,  // X coordinate for move actions
@@ -44,13 +243,13 @@ pub struct MouseSimulationEvent {
     pub ease: Option<String>, // Easing function name ("linear", "easeInQuad", etc.)
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct TextSimulationEvent {
     pub text: String,
     pub delay_after_ms: Option<u64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(tag = "event_type")]
 pub enum SimulationEvent {
     #[serde(rename = "key")]
@@ -61,20 +260,184 @@ pub enum SimulationEvent {
     Text(TextSimulationEvent),
 }
 
-#[derive(Serialize)]
-pub struct MouseEvent {
-    pub event_type: String, // "move", "click", "scroll", etc.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub x: Option<i32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub y: Option<i32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub button: Option<String>, // "left", "right", "middle" for click events
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub pressed: Option<bool>, // true for press, false for release
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub delta_x: Option<i32>, // Horizontal scroll delta
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub delta_y: Option<i32>, // Vertical scroll delta
-    pub timestamp: u64,
-}
\ No newline at end of file
+/// The pointer moved to an absolute screen position.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct MouseMoveEvent {
+    pub x: i32,
+    pub y: i32,
+    #[serde(flatten)]
+    pub time: EventTime,
+}
+
+/// A mouse button was pressed or released.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct MouseButtonEvent {
+    pub button: String, // "left", "right", "middle" or "button_<code>"
+    pub pressed: bool, // true for press, false for release
+    #[serde(default)]
+    pub modifiers: Modifiers, // For Ctrl+click and the like
+    #[serde(flatten)]
+    pub time: EventTime,
+}
+
+/// The wheel was turned.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct MouseScrollEvent {
+    pub delta_x: i32, // Horizontal scroll delta
+    pub delta_y: i32, // Vertical scroll delta
+    #[serde(default)]
+    pub modifiers: Modifiers, // For Shift+scroll and the like
+    #[serde(flatten)]
+    pub time: EventTime,
+}
+
+/// First record of every output stream, describing the build so clients can
+/// check what they launched.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Hello {
+    pub version: String, // Crate version
+    pub protocol_version: u32,
+    pub modes: Vec<String>, // Modes running in this process
+    pub features: Vec<String>, // Cargo features compiled in
+    pub keys: Vec<KeyName>, // Canonical key names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actions: Option<BTreeMap<String, Vec<String>>>, // Simulation actions by event_type, if compiled in
+}
+
+/// Emitted / failed record counts, written periodically and when the stream ends.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Summary {
+    pub emitted: u64, // Records written successfully, not counting summaries
+    pub failed: u64, // Records that could not be written, not counting summaries; their `seq` is missing from the stream
+    #[serde(rename = "final")]
+    pub is_final: bool, // Last summary before the process exits
+}
+
+/// Everything a listener mode can emit, discriminated by `event_type`.
+///
+/// Each tag has exactly one meaning and one payload, whichever mode produced it.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+pub enum OutputEvent {
+    /// A key went down or up (direct, complex and combined modes).
+    Key(KeyEvent),
+    /// The first key of a new combination went down (complex mode).
+    CombinationPress(ComplexKeyEvent),
+    /// A key joined or left a combination while others stay held (complex mode).
+    Combination(ComplexKeyEvent),
+    /// The last key of a combination was released; `keys` is the final combination (complex mode).
+    CombinationRelease(ComplexKeyEvent),
+    /// All keys of a sequence were released (hold_and_release mode).
+    KeySequence(KeySequenceEvent),
+    /// A configured hotkey went down or came back up (hotkey mode).
+    Hotkey(HotkeyEvent),
+    /// A multi-step key sequence matched, timed out or was aborted (sequence mode).
+    Sequence(SequenceEvent),
+    /// A key was tapped, held or long-pressed (gesture mode).
+    Gesture(GestureEvent),
+    /// Typed text was committed (text mode).
+    TextCommit(TextCommitEvent),
+    /// Typing statistics, periodically and when the stream ends (stats mode).
+    Stats(StatsEvent),
+    /// The pointer moved (mouse and combined modes).
+    MouseMove(MouseMoveEvent),
+    /// A mouse button was pressed or released (mouse and combined modes).
+    MouseButton(MouseButtonEvent),
+    /// The wheel was turned (mouse and combined modes).
+    MouseScroll(MouseScrollEvent),
+    /// Opens every stream.
+    Hello(Hello),
+    /// Record counts, periodically and at exit.
+    Summary(Summary),
+}
+
+impl From<KeyEvent> for OutputEvent {
+    fn from(event: KeyEvent) -> Self {
+        OutputEvent::Key(event)
+    }
+}
+
+impl From<KeySequenceEvent> for OutputEvent {
+    fn from(event: KeySequenceEvent) -> Self {
+        OutputEvent::KeySequence(event)
+    }
+}
+
+impl From<HotkeyEvent> for OutputEvent {
+    fn from(event: HotkeyEvent) -> Self {
+        OutputEvent::Hotkey(event)
+    }
+}
+
+impl From<SequenceEvent> for OutputEvent {
+    fn from(event: SequenceEvent) -> Self {
+        OutputEvent::Sequence(event)
+    }
+}
+
+impl From<GestureEvent> for OutputEvent {
+    fn from(event: GestureEvent) -> Self {
+        OutputEvent::Gesture(event)
+    }
+}
+
+impl From<TextCommitEvent> for OutputEvent {
+    fn from(event: TextCommitEvent) -> Self {
+        OutputEvent::TextCommit(event)
+    }
+}
+
+impl From<StatsEvent> for OutputEvent {
+    fn from(event: StatsEvent) -> Self {
+        OutputEvent::Stats(event)
+    }
+}
+
+impl From<MouseMoveEvent> for OutputEvent {
+    fn from(event: MouseMoveEvent) -> Self {
+        OutputEvent::MouseMove(event)
+    }
+}
+
+impl From<MouseButtonEvent> for OutputEvent {
+    fn from(event: MouseButtonEvent) -> Self {
+        OutputEvent::MouseButton(event)
+    }
+}
+
+impl From<MouseScrollEvent> for OutputEvent {
+    fn from(event: MouseScrollEvent) -> Self {
+        OutputEvent::MouseScroll(event)
+    }
+}
+
+impl From<Hello> for OutputEvent {
+    fn from(event: Hello) -> Self {
+        OutputEvent::Hello(event)
+    }
+}
+
+impl From<Summary> for OutputEvent {
+    fn from(event: Summary) -> Self {
+        OutputEvent::Summary(event)
+    }
+}
+
+// One line of output: the event plus the metadata the runner attaches to it
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Record {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>, // Position in the output stream, assigned by `SequencedSink`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>, // Producing mode, set when several modes share one stream
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub injected: bool, // Duplex: the event was generated by this process's own simulation
+    #[serde(flatten)]
+    pub event: OutputEvent,
+}
+
+impl From<OutputEvent> for Record {
+    fn from(event: OutputEvent) -> Self {
+        Record { seq: None, mode: None, injected: false, event }
+    }
+}
@@ -0,0 +1,61 @@
+use rdev::{Event, EventType, Key};
+use crate::models::Modifiers;
+
+/// Follows the modifier and lock keys through the event stream.
+///
+/// The lock states count toggles since the listener started, so they assume
+/// Caps Lock and Num Lock were off at that point; rdev cannot read the LEDs.
+#[derive(Debug, Default)]
+pub struct ModifierTracker {
+    state: Modifiers,
+    caps_lock_held: bool, // So auto-repeat does not toggle again
+    num_lock_held: bool,
+}
+
+impl ModifierTracker {
+    /// The state after every event seen so far.
+    pub fn current(&self) -> Modifiers {
+        self.state
+    }
+
+    /// Applies a key press or release; other events change nothing.
+    pub fn update(&mut self, event: &Event) {
+        let (key, pressed) = match event.event_type {
+            EventType::KeyPress(key) => (key, true),
+            EventType::KeyRelease(key) => (key, false),
+            _ => return,
+        };
+
+        let state = &mut self.state;
+        match key {
+            Key::ShiftLeft => state.shift_left = pressed,
+            Key::ShiftRight => state.shift_right = pressed,
+            Key::ControlLeft => state.ctrl_left = pressed,
+            Key::ControlRight => state.ctrl_right = pressed,
+            Key::Alt => state.alt_left = pressed,
+            Key::AltGr => {
+                state.alt_right = pressed;
+                state.alt_gr = pressed;
+            }
+            Key::MetaLeft => state.meta_left = pressed,
+            Key::MetaRight => state.meta_right = pressed,
+            Key::CapsLock => {
+                if pressed && !self.caps_lock_held {
+                    state.caps_lock = !state.caps_lock;
+                }
+                self.caps_lock_held = pressed;
+            }
+            Key::NumLock => {
+                if pressed && !self.num_lock_held {
+                    state.num_lock = !state.num_lock;
+                }
+                self.num_lock_held = pressed;
+            }
+            _ => return,
+        }
+        state.shift = state.shift_left || state.shift_right;
+        state.ctrl = state.ctrl_left || state.ctrl_right;
+        state.alt = state.alt_left || state.alt_right;
+        state.meta = state.meta_left || state.meta_right;
+    }
+}
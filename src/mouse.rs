@@ -1,77 +1,74 @@
-use rdev::{listen, Event, EventType, Button};
-use crate::models::MouseEvent;
-use chrono::Utc;
+use rdev::{Event, EventType};
+use crate::clock::EventTime;
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::keys::button_name;
+use crate::mode::Mode;
+use crate::models::{Modifiers, MouseButtonEvent, MouseMoveEvent, MouseScrollEvent, OutputEvent};
+use crate::modifiers::ModifierTracker;
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
 
-// Helper function to map rdev Button to string
-fn button_to_string(button: Button) -> String {
-    match button {
-        Button::Left => "left".to_string(),
-        Button::Right => "right".to_string(),
-        Button::Middle => "middle".to_string(),
-        Button::Unknown(code) => format!("button_{}", code), // Handle unknown buttons
+// Maps a move, button or wheel event to the matching output event, ignoring keyboard events
+pub fn mouse_event(event: &Event, time: EventTime, modifiers: Modifiers) -> Option<OutputEvent> {
+    match event.event_type {
+        EventType::MouseMove { x, y } => Some(MouseMoveEvent {
+            x: x as i32,
+            y: y as i32,
+            time,
+        }.into()),
+        EventType::ButtonPress(button) => Some(MouseButtonEvent {
+            button: button_name(button),
+            pressed: true,
+            modifiers,
+            time,
+        }.into()),
+        EventType::ButtonRelease(button) => Some(MouseButtonEvent {
+            button: button_name(button),
+            pressed: false,
+            modifiers,
+            time,
+        }.into()),
+        EventType::Wheel { delta_x, delta_y } => Some(MouseScrollEvent {
+            delta_x: delta_x as i32, // Cast i64 to i32
+            delta_y: delta_y as i32, // Cast i64 to i32
+            modifiers,
+            time,
+        }.into()),
+        // Ignore keyboard events in this handler
+        EventType::KeyPress(_) | EventType::KeyRelease(_) => None,
     }
 }
 
-// Callback function to process events
-fn callback(event: Event) {
-    let current_time = Utc::now().timestamp_millis() as u64;
-    let mouse_event = match event.event_type {
-        EventType::MouseMove { x, y } => Some(MouseEvent {
-            event_type: "move".to_string(),
-            x: Some(x as i32), // Wrap in Some
-            y: Some(y as i32), // Wrap in Some
-            button: None,
-            pressed: None,
-            delta_x: None,
-            delta_y: None,
-            timestamp: current_time,
-        }),
-        EventType::ButtonPress(button) => Some(MouseEvent {
-            event_type: "button".to_string(),
-            // Coordinates are not directly available for button events in rdev
-            x: None, // Set to None
-            y: None, // Set to None
-            button: Some(button_to_string(button)),
-            pressed: Some(true),
-            delta_x: None,
-            delta_y: None,
-            timestamp: current_time,
-        }),
-        EventType::ButtonRelease(button) => Some(MouseEvent {
-            event_type: "button".to_string(),
-            // Coordinates are not directly available for button events in rdev
-            x: None, // Set to None
-            y: None, // Set to None
-            button: Some(button_to_string(button)),
-            pressed: Some(false),
-            delta_x: None,
-            delta_y: None,
-            timestamp: current_time,
-        }),
-        EventType::Wheel { delta_x, delta_y } => Some(MouseEvent {
-            event_type: "scroll".to_string(),
-            // Coordinates are not directly available for wheel events in rdev
-            x: None, // Set to None
-            y: None, // Set to None
-            button: None,
-            pressed: None,
-            delta_x: Some(delta_x as i32), // Cast i64 to i32
-            delta_y: Some(delta_y as i32), // Cast i64 to i32
-            timestamp: current_time,
-        }),
-        // Ignore keyboard events in this handler
-        EventType::KeyPress(_) | EventType::KeyRelease(_) => None,
-    };
+// Emits mouse movement, button and wheel events. Key events are only
+// followed for the modifiers held during clicks and scrolls.
+#[derive(Debug, Default)]
+pub struct MouseHandler {
+    modifiers: ModifierTracker,
+}
 
-    if let Some(me) = mouse_event {
-        if let Ok(json) = serde_json::to_string(&me) {
-            println!("{}", json);
+impl Handler for MouseHandler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        self.modifiers.update(event);
+        if let Some(me) = mouse_event(event, EventTime::of(event), self.modifiers.current()) {
+            out.push(me);
         }
     }
+
+    fn observe(&mut self, event: &Event) {
+        self.modifiers.update(event);
+    }
 }
 
 pub fn mouse_handling() {
-    if let Err(error) = listen(callback) {
-        eprintln!("Error listening for mouse events: {:?}", error);
+    mouse_handling_with(&Options::from_env());
+}
+
+pub fn mouse_handling_with(options: &Options) {
+    let sink = open_stdout(options, &[Mode::Mouse]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(MouseHandler::default(), options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for mouse events: {}", error);
     }
-}
\ No newline at end of file
+    sink.finish();
+}
@@ -0,0 +1,185 @@
+use crate::clock::TimeFormat;
+use crate::filter::EventFilter;
+use crate::format::Format;
+use std::env;
+use std::fs;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+// Command-line flags shared by all modes. Positional arguments (the mode list)
+// are handed back to the caller untouched.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub suppress_injected: bool, // Duplex: drop events this process injected instead of marking them
+    pub suppress_repeats: bool, // Direct: drop OS auto-repeat presses instead of marking them
+    pub timeline: bool, // Hold and release: attach every press and release to key_sequence events
+    pub format: Format, // Encoding of records on stdout and simulation commands on stdin
+    pub time_format: TimeFormat, // How wall-clock timestamps are written
+    pub summary_interval: Option<Duration>, // Write a summary record this often, besides the one at exit
+    pub filter: EventFilter, // Keys and buttons the listener modes see
+    pub hotkeys: Vec<String>, // Hotkey mode: `name = Keys` bindings
+    pub sequences: Vec<String>, // Sequence mode: `name = Keys Keys...` bindings
+    pub sequence_timeout: Option<Duration>, // Sequence mode: longest pause between steps
+    pub tap_max: Option<Duration>, // Gesture mode: longest press that counts as a tap
+    pub tap_gap: Option<Duration>, // Gesture mode: longest pause between taps of a run
+    pub long_press: Option<Duration>, // Gesture mode: shortest long press
+    pub text_idle: Option<Duration>, // Text mode: pause after which typed text is committed
+    pub stats_interval: Option<Duration>, // Stats mode: how often to write statistics; zero for only at exit
+}
+
+// Parses the value of `--flag`, reporting a missing or malformed one
+fn parse_value<T>(flag: &str, value: Option<String>) -> Option<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let Some(value) = value else {
+        eprintln!("Missing value for --{}", flag);
+        return None;
+    };
+    match value.parse() {
+        Ok(parsed) => Some(parsed),
+        Err(error) => {
+            eprintln!("Invalid value for --{}: {}", flag, error);
+            None
+        }
+    }
+}
+
+// Parses a comma-separated `--flag` value, reporting and skipping bad entries
+fn parse_list<T>(flag: &str, value: Option<String>) -> Vec<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let Some(value) = value else {
+        eprintln!("Missing value for --{}", flag);
+        return Vec::new();
+    };
+    value
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .filter_map(|item| match item.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(error) => {
+                eprintln!("Invalid value for --{}: {}", flag, error);
+                None
+            }
+        })
+        .collect()
+}
+
+// Reads bindings from the file named by `--flag`, one per line, skipping blank
+// lines and `#` comments
+fn read_bindings(flag: &str, path: Option<String>) -> Vec<String> {
+    let Some(path) = path else {
+        eprintln!("Missing value for --{}", flag);
+        return Vec::new();
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+        Err(error) => {
+            eprintln!("Error reading {}: {}", path, error);
+            Vec::new()
+        }
+    }
+}
+
+impl Options {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> (Options, Vec<String>) {
+        let mut options = Options::default();
+        let mut positional = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                positional.push(arg);
+                continue;
+            };
+            // Valued flags take `--flag value` or `--flag=value`
+            let (flag, inline_value) = match flag.split_once('=') {
+                Some((flag, value)) => (flag, Some(value.to_string())),
+                None => (flag, None),
+            };
+            let mut value = || inline_value.clone().or_else(|| args.next());
+            match flag {
+                "suppress-injected" => options.suppress_injected = true,
+                "suppress-repeats" => options.suppress_repeats = true,
+                "timeline" => options.timeline = true,
+                "format" => {
+                    if let Some(format) = parse_value(flag, value()) {
+                        options.format = format;
+                    }
+                }
+                "time-format" => {
+                    if let Some(time_format) = parse_value(flag, value()) {
+                        options.time_format = time_format;
+                    }
+                }
+                "summary-interval" => {
+                    if let Some(seconds) = parse_value::<u64>(flag, value()) {
+                        options.summary_interval = (seconds > 0).then(|| Duration::from_secs(seconds));
+                    }
+                }
+                // An include flag always activates its list, so bad patterns filter out everything
+                "include-keys" => options.filter.include_keys.get_or_insert_with(Vec::new).extend(parse_list(flag, value())),
+                "exclude-keys" => options.filter.exclude_keys.extend(parse_list(flag, value())),
+                "include-buttons" => options.filter.include_buttons.get_or_insert_with(Vec::new).extend(parse_list(flag, value())),
+                "exclude-buttons" => options.filter.exclude_buttons.extend(parse_list(flag, value())),
+                "hotkey" => match value() {
+                    Some(binding) => options.hotkeys.push(binding),
+                    None => eprintln!("Missing value for --{}", flag),
+                },
+                "hotkeys-file" => options.hotkeys.extend(read_bindings(flag, value())),
+                "sequence" => match value() {
+                    Some(binding) => options.sequences.push(binding),
+                    None => eprintln!("Missing value for --{}", flag),
+                },
+                "sequences-file" => options.sequences.extend(read_bindings(flag, value())),
+                "sequence-timeout" => {
+                    if let Some(millis) = parse_value(flag, value()) {
+                        options.sequence_timeout = Some(Duration::from_millis(millis));
+                    }
+                }
+                "tap-max" => {
+                    if let Some(millis) = parse_value(flag, value()) {
+                        options.tap_max = Some(Duration::from_millis(millis));
+                    }
+                }
+                "tap-gap" => {
+                    if let Some(millis) = parse_value(flag, value()) {
+                        options.tap_gap = Some(Duration::from_millis(millis));
+                    }
+                }
+                "long-press" => {
+                    if let Some(millis) = parse_value(flag, value()) {
+                        options.long_press = Some(Duration::from_millis(millis));
+                    }
+                }
+                "stats-interval" => {
+                    if let Some(seconds) = parse_value(flag, value()) {
+                        options.stats_interval = Some(Duration::from_secs(seconds));
+                    }
+                }
+                "text-idle" => {
+                    if let Some(millis) = parse_value(flag, value()) {
+                        options.text_idle = Some(Duration::from_millis(millis));
+                    }
+                }
+                _ => eprintln!("Unknown option: --{}", flag),
+            }
+        }
+
+        (options, positional)
+    }
+
+    // Options from the process arguments, skipping the program name
+    pub fn from_env() -> Self {
+        Self::parse(env::args().skip(1)).0
+    }
+}
@@ -0,0 +1,38 @@
+use crate::handler::finish_running;
+use crate::hello::announce;
+use crate::mode::Mode;
+use crate::options::Options;
+use crate::sink::{SequencedSink, StdoutSink};
+use std::process;
+use std::thread;
+
+// Exit status after Ctrl+C, as a shell reports SIGINT
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+// Opens the numbered stdout stream shared by every mode: writes the hello
+// record, starts periodic summaries if requested and makes sure the handlers'
+// final output and a final summary are written when the process is interrupted. Call `finish` on the
+// returned sink when the mode returns normally.
+pub fn open_stdout(options: &Options, modes: &[Mode]) -> SequencedSink {
+    let mut sink = SequencedSink::new(StdoutSink::new(options.format).with_time_format(options.time_format));
+    announce(&mut sink, modes);
+
+    if let Some(interval) = options.summary_interval {
+        let summary_sink = sink.clone();
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            summary_sink.emit_summary();
+        });
+    }
+
+    let interrupted_sink = sink.clone();
+    if let Err(error) = ctrlc::set_handler(move || {
+        finish_running();
+        interrupted_sink.finish();
+        process::exit(INTERRUPTED_EXIT_CODE);
+    }) {
+        eprintln!("Error installing interrupt handler: {}", error);
+    }
+
+    sink
+}
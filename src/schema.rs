@@ -0,0 +1,22 @@
+use crate::models::{Record, SimulationEvent};
+use schemars::generate::SchemaSettings;
+use serde_json::{json, Value};
+
+/// JSON Schema for one output record, i.e. one line written by a listener mode.
+pub fn output_schema() -> Value {
+    SchemaSettings::draft2020_12().for_serialize().into_generator().into_root_schema_for::<Record>().to_value()
+}
+
+/// JSON Schema for one simulation command read from stdin.
+pub fn input_schema() -> Value {
+    SchemaSettings::draft2020_12().for_deserialize().into_generator().into_root_schema_for::<SimulationEvent>().to_value()
+}
+
+/// Both directions of the wire protocol in one document, as printed by the
+/// `schema` subcommand and checked in as `schema/wire.schema.json`.
+pub fn wire_schema() -> Value {
+    json!({
+        "output": output_schema(),
+        "input": input_schema(),
+    })
+}
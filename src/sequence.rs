@@ -0,0 +1,234 @@
+use rdev::{Event, EventType};
+use crate::clock::EventTime;
+use crate::complex::HeldKeys;
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::hotkey::{Chord, Modifier};
+use crate::keys::KeyName;
+use crate::mode::Mode;
+use crate::models::{OutputEvent, SequenceEvent, SequenceOutcome};
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How long to wait for the next step unless `--sequence-timeout` says otherwise.
+pub const DEFAULT_STEP_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// A named chain of chords such as `comment = Ctrl+K Ctrl+C` or `top = g g`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySequence {
+    pub name: String,
+    pub steps: Vec<Chord>,
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} =", self.name)?;
+        for step in &self.steps {
+            write!(f, " {}", step)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, keys) = s.split_once('=').ok_or_else(|| format!("Expected name = Keys Keys..., got: {}", s))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("Sequence without a name: {}", s));
+        }
+
+        // Steps are separated by spaces; spaces around `+` stay inside a step
+        let mut steps: Vec<String> = Vec::new();
+        for token in keys.split_whitespace() {
+            match steps.last_mut() {
+                Some(step) if step.ends_with('+') || token.starts_with('+') => step.push_str(token),
+                _ => steps.push(token.to_string()),
+            }
+        }
+        if steps.is_empty() {
+            return Err(format!("Sequence {} has no steps", name));
+        }
+        let steps = steps
+            .iter()
+            .map(|step| step.parse())
+            .collect::<Result<_, String>>()
+            .map_err(|error| format!("Sequence {}: {}", name, error))?;
+
+        Ok(KeySequence { name: name.to_string(), steps })
+    }
+}
+
+// Parses `name = Keys Keys...` bindings, reporting and skipping the ones that do not parse
+pub fn parse_sequences(bindings: &[String]) -> Vec<KeySequence> {
+    bindings
+        .iter()
+        .filter_map(|binding| match binding.parse() {
+            Ok(sequence) => Some(sequence),
+            Err(error) => {
+                eprintln!("Invalid sequence: {}", error);
+                None
+            }
+        })
+        .collect()
+}
+
+// The bindings given in `options`, or why sequence mode cannot run without any
+pub fn sequences_from(options: &Options) -> Result<Vec<KeySequence>, String> {
+    let sequences = parse_sequences(&options.sequences);
+    if sequences.is_empty() {
+        return Err("Sequence mode needs at least one binding, e.g. --sequence \"comment = Ctrl+K Ctrl+C\"".to_string());
+    }
+    Ok(sequences)
+}
+
+// Prefix tree of bindings, one level per step
+#[derive(Debug, Default)]
+struct Node {
+    binding: Option<String>, // Sequence ending here
+    children: HashMap<Chord, Node>,
+}
+
+// Walks the prefix tree one chord at a time. A step that leads nowhere aborts
+// the sequence; a pause longer than the timeout ends it, as a match if the
+// steps so far form a binding of their own (`g` next to `g g`) and as a
+// timeout otherwise.
+#[derive(Debug, Default)]
+pub struct SequenceHandler {
+    root: Node,
+    timeout: Duration,
+    held: HeldKeys,
+    path: Vec<Chord>, // Steps of the sequence in progress
+    deadline: Option<Duration>,
+}
+
+impl SequenceHandler {
+    pub fn new(sequences: Vec<KeySequence>, timeout: Duration) -> Self {
+        let mut root = Node::default();
+        for sequence in sequences {
+            let node = sequence.steps.iter().fold(&mut root, |node, step| node.children.entry(step.clone()).or_default());
+            if let Some(previous) = node.binding.replace(sequence.name.clone()) {
+                eprintln!("Sequence {} replaces {}, which has the same steps", sequence.name, previous);
+            }
+        }
+        SequenceHandler { root, timeout, ..Self::default() }
+    }
+
+    fn node(&self) -> &Node {
+        self.path.iter().fold(&self.root, |node, step| &node.children[step])
+    }
+
+    // Reports the sequence in progress and starts over
+    fn conclude(&mut self, outcome: SequenceOutcome, sequence: Option<String>, extra_step: Option<&Chord>, time: EventTime, out: &mut Vec<OutputEvent>) {
+        let steps = self.path.iter().chain(extra_step).map(Chord::to_string).collect();
+        out.push(SequenceEvent { sequence, outcome, steps, time }.into());
+        self.path.clear();
+        self.deadline = None;
+    }
+
+    // Ends the sequence in progress for lack of a next step
+    fn time_out(&mut self, time: EventTime, out: &mut Vec<OutputEvent>) {
+        match self.node().binding.clone() {
+            Some(sequence) => self.conclude(SequenceOutcome::Match, Some(sequence), None, time, out),
+            None => self.conclude(SequenceOutcome::Timeout, None, None, time, out),
+        }
+    }
+
+    // Takes the chords a key press typed, strictest first
+    fn step(&mut self, chords: Vec<Chord>, time: EventTime, out: &mut Vec<OutputEvent>) {
+        if let Some(chord) = chords.iter().find(|chord| self.node().children.contains_key(chord)) {
+            self.path.push(chord.clone());
+            let node = self.node();
+            if node.children.is_empty() {
+                let sequence = node.binding.clone();
+                self.conclude(SequenceOutcome::Match, sequence, None, time, out);
+            } else {
+                self.deadline = Some(Duration::from_micros(time.monotonic_us) + self.timeout);
+            }
+            return;
+        }
+
+        // A key that starts no sequence
+        if self.path.is_empty() {
+            return;
+        }
+
+        // The key breaks off the sequence in progress, unless that is already a
+        // binding waiting for its timeout
+        match self.node().binding.clone() {
+            Some(sequence) => self.conclude(SequenceOutcome::Match, Some(sequence), None, time, out),
+            None => self.conclude(SequenceOutcome::Abort, None, chords.first(), time, out),
+        }
+        // It may still start the next one
+        if !chords.is_empty() {
+            self.step(chords, time, out);
+        }
+    }
+}
+
+impl Handler for SequenceHandler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        match event.event_type {
+            EventType::KeyPress(key) => {
+                // Auto-repeat does not count as another step
+                if !self.held.press(key) {
+                    return;
+                }
+                // Modifiers only count as part of the chord they are held for
+                let key_name = KeyName::from(key);
+                if Modifier::of(key_name).is_some() {
+                    return;
+                }
+                let chords = Chord::pressed(self.held.keys(), key_name);
+                self.step(chords, EventTime::of(event), out);
+            }
+            EventType::KeyRelease(key) => {
+                self.held.release(key);
+            }
+            _ => {} // Ignore other event types
+        }
+    }
+
+    fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    fn expire(&mut self, now: Duration, out: &mut Vec<OutputEvent>) {
+        self.time_out(EventTime::at(self.deadline.unwrap_or(now)), out);
+    }
+
+    // The stream ending cuts the sequence in progress short
+    fn finish(&mut self, out: &mut Vec<OutputEvent>) {
+        if !self.path.is_empty() {
+            self.time_out(EventTime::now(), out);
+        }
+    }
+}
+
+pub fn sequence_handling() {
+    sequence_handling_with(&Options::from_env());
+}
+
+pub fn sequence_handling_with(options: &Options) {
+    let sequences = match sequences_from(options) {
+        Ok(sequences) => sequences,
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
+
+    let handler = SequenceHandler::new(sequences, options.sequence_timeout.unwrap_or(DEFAULT_STEP_TIMEOUT));
+    let sink = open_stdout(options, &[Mode::Sequence]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(handler, options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for keyboard events: {}", error);
+    }
+    sink.finish();
+}
@@ -1,171 +1,239 @@
-use std::{io::{self, BufRead}, thread, time::{Duration, Instant}};
-use enigo::{Enigo, Key, Keyboard, Mouse, Settings};
+use std::{io::{self, BufRead}, process, time::Duration};
+use enigo::Key;
 
+use crate::backend::{EnigoBackend, InputBackend};
+use crate::format::Format;
+use crate::keys::KeyName;
+use crate::mode::Mode;
 use crate::models::{KeySimulationEvent, MouseSimulationEvent, SimulationEvent, TextSimulationEvent};
+use crate::options::Options;
+use crate::output::open_stdout;
 
+/// Actions accepted by key commands.
+pub const KEY_ACTIONS: &[&str] = &["press", "release", "tap"];
+/// Actions accepted by mouse commands.
+pub const MOUSE_ACTIONS: &[&str] = &["move", "click", "press", "release", "scroll"];
+
+// Resolves a simulation key name. Single characters are typed as-is, so case
+// and non-ASCII text survive; anything longer goes through the shared key table.
 pub fn string_to_key(key_str: &str) -> Option<Key> {
-    match key_str {
-        "F1" => Some(Key::F1),
-        "F2" => Some(Key::F2),
-        "F3" => Some(Key::F3),
-        "F4" => Some(Key::F4),
-        "F5" => Some(Key::F5),
-        "F6" => Some(Key::F6),
-        "F7" => Some(Key::F7),
-        "F8" => Some(Key::F8),
-        "F9" => Some(Key::F9),
-        "F10" => Some(Key::F10),
-        "F11" => Some(Key::F11),
-        "F12" => Some(Key::F12),
-        "F13" => Some(Key::F13),
-        "F14" => Some(Key::F14),
-        "F15" => Some(Key::F15),
-        "F16" => Some(Key::F16),
-        "F17" => Some(Key::F17),
-        "F18" => Some(Key::F18),
-        "F19" => Some(Key::F19),
-        "F20" => Some(Key::F20),
-        
-        "Home" => Some(Key::Home),
-        "End" => Some(Key::End),
-        "PageUp" => Some(Key::PageUp),
-        "PageDown" => Some(Key::PageDown),
-        "Delete" => Some(Key::Delete),
-        "Escape" => Some(Key::Escape),
-        "Tab" => Some(Key::Tab),
-        "Return" => Some(Key::Return),
-        "Space" => Some(Key::Space),
-        "Backspace" => Some(Key::Backspace),
-        
+    let mut chars = key_str.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Unicode(c));
+    }
+
+    key_str.parse::<KeyName>().ok().and_then(enigo_key)
+}
+
+// The enigo key that reproduces `key`, warning when this platform cannot
+// simulate it
+pub fn enigo_key(key: KeyName) -> Option<Key> {
+    let enigo_key = simulated_key(key);
+    if enigo_key.is_none() {
+        eprintln!("Warning: {} key cannot be simulated on this platform", key);
+    }
+    enigo_key
+}
+
+// The enigo key that reproduces `key`, if this platform can simulate it
+pub fn simulated_key(key: KeyName) -> Option<Key> {
+    match key {
+        KeyName::F1 => Some(Key::F1),
+        KeyName::F2 => Some(Key::F2),
+        KeyName::F3 => Some(Key::F3),
+        KeyName::F4 => Some(Key::F4),
+        KeyName::F5 => Some(Key::F5),
+        KeyName::F6 => Some(Key::F6),
+        KeyName::F7 => Some(Key::F7),
+        KeyName::F8 => Some(Key::F8),
+        KeyName::F9 => Some(Key::F9),
+        KeyName::F10 => Some(Key::F10),
+        KeyName::F11 => Some(Key::F11),
+        KeyName::F12 => Some(Key::F12),
+        KeyName::F13 => Some(Key::F13),
+        KeyName::F14 => Some(Key::F14),
+        KeyName::F15 => Some(Key::F15),
+        KeyName::F16 => Some(Key::F16),
+        KeyName::F17 => Some(Key::F17),
+        KeyName::F18 => Some(Key::F18),
+        KeyName::F19 => Some(Key::F19),
+        KeyName::F20 => Some(Key::F20),
+
+        KeyName::Home => Some(Key::Home),
+        KeyName::End => Some(Key::End),
+        KeyName::PageUp => Some(Key::PageUp),
+        KeyName::PageDown => Some(Key::PageDown),
+        KeyName::Delete => Some(Key::Delete),
+        KeyName::Escape => Some(Key::Escape),
+        KeyName::Tab => Some(Key::Tab),
+        KeyName::Return | KeyName::KpReturn => Some(Key::Return),
+        KeyName::Space => Some(Key::Space),
+        KeyName::Backspace => Some(Key::Backspace),
+
         #[cfg(not(target_os = "macos"))]
-        "Insert" => Some(Key::Insert),
+        KeyName::Insert => Some(Key::Insert),
         #[cfg(not(target_os = "macos"))]
-        "PrintScr" => Some(Key::PrintScr),
-        
-        #[cfg(target_os = "macos")]
-        "Insert" => {
-            eprintln!("Warning: Insert key not supported on macOS");
-            None
-        },
-        #[cfg(target_os = "macos")]
-        "PrintScr" => {
-            eprintln!("Warning: PrintScr key not supported on macOS");
-            None
-        },
-        
-        "UpArrow" => Some(Key::UpArrow),
-        "DownArrow" => Some(Key::DownArrow),
-        "LeftArrow" => Some(Key::LeftArrow),
-        "RightArrow" => Some(Key::RightArrow),
-        
-        "Alt" => Some(Key::Alt),
-        "Control" => Some(Key::Control),
-        "Shift" => Some(Key::Shift),
-        "Meta" => Some(Key::Meta),
-        "Option" => Some(Key::Option),
-        "CapsLock" => Some(Key::CapsLock),
-        
-        "VolumeUp" => Some(Key::VolumeUp),
-        "VolumeDown" => Some(Key::VolumeDown),
-        "VolumeMute" => Some(Key::VolumeMute),
-        "MediaPlayPause" => Some(Key::MediaPlayPause),
-        "MediaNextTrack" => Some(Key::MediaNextTrack),
-        "MediaPrevTrack" => Some(Key::MediaPrevTrack),
-        
-        #[cfg(target_os = "windows")]
-        "Numpad0" => Some(Key::Numpad0),
-        #[cfg(target_os = "windows")]
-        "Numpad1" => Some(Key::Numpad1),
-        #[cfg(target_os = "windows")]
-        "Numpad2" => Some(Key::Numpad2),
+        KeyName::PrintScreen => Some(Key::PrintScr),
+        #[cfg(not(target_os = "macos"))]
+        KeyName::Pause => Some(Key::Pause),
+        #[cfg(not(target_os = "macos"))]
+        KeyName::NumLock => Some(Key::Numlock),
+        #[cfg(all(unix, not(target_os = "macos")))]
+        KeyName::ScrollLock => Some(Key::ScrollLock),
+
+        KeyName::UpArrow => Some(Key::UpArrow),
+        KeyName::DownArrow => Some(Key::DownArrow),
+        KeyName::LeftArrow => Some(Key::LeftArrow),
+        KeyName::RightArrow => Some(Key::RightArrow),
+
+        KeyName::Alt => Some(Key::Alt),
         #[cfg(target_os = "windows")]
-        "Numpad3" => Some(Key::Numpad3),
+        KeyName::AltGr => Some(Key::RMenu),
+        #[cfg(target_os = "macos")]
+        KeyName::AltGr => Some(Key::ROption),
+        #[cfg(all(unix, not(target_os = "macos")))]
+        KeyName::AltGr => Some(Key::Other(0xfe03)), // XK_ISO_Level3_Shift
+        KeyName::ControlLeft => Some(Key::LControl),
+        KeyName::ControlRight => Some(Key::RControl),
+        KeyName::ShiftLeft => Some(Key::LShift),
+        KeyName::ShiftRight => Some(Key::RShift),
+        KeyName::MetaLeft => Some(Key::Meta),
         #[cfg(target_os = "windows")]
-        "Numpad4" => Some(Key::Numpad4),
+        KeyName::MetaRight => Some(Key::RWin),
+        #[cfg(target_os = "macos")]
+        KeyName::MetaRight => Some(Key::RCommand),
+        #[cfg(all(unix, not(target_os = "macos")))]
+        KeyName::MetaRight => Some(Key::Other(0xffec)), // XK_Super_R
+        KeyName::CapsLock => Some(Key::CapsLock),
+        #[cfg(target_os = "macos")]
+        KeyName::Function => Some(Key::Function),
+
+        KeyName::VolumeUp => Some(Key::VolumeUp),
+        KeyName::VolumeDown => Some(Key::VolumeDown),
+        KeyName::VolumeMute => Some(Key::VolumeMute),
+        KeyName::MediaPlayPause => Some(Key::MediaPlayPause),
+        KeyName::MediaNextTrack => Some(Key::MediaNextTrack),
+        KeyName::MediaPrevTrack => Some(Key::MediaPrevTrack),
+
+        KeyName::Help => Some(Key::Help),
+
         #[cfg(target_os = "windows")]
-        "Numpad5" => Some(Key::Numpad5),
+        KeyName::Kp0 => Some(Key::Numpad0),
         #[cfg(target_os = "windows")]
-        "Numpad6" => Some(Key::Numpad6),
+        KeyName::Kp1 => Some(Key::Numpad1),
         #[cfg(target_os = "windows")]
-        "Numpad7" => Some(Key::Numpad7),
+        KeyName::Kp2 => Some(Key::Numpad2),
         #[cfg(target_os = "windows")]
-        "Numpad8" => Some(Key::Numpad8),
+        KeyName::Kp3 => Some(Key::Numpad3),
         #[cfg(target_os = "windows")]
-        "Numpad9" => Some(Key::Numpad9),
-        
+        KeyName::Kp4 => Some(Key::Numpad4),
         #[cfg(target_os = "windows")]
-        "Num0" => Some(Key::Num0),
+        KeyName::Kp5 => Some(Key::Numpad5),
         #[cfg(target_os = "windows")]
-        "Num1" => Some(Key::Num1),
+        KeyName::Kp6 => Some(Key::Numpad6),
         #[cfg(target_os = "windows")]
-        "Num2" => Some(Key::Num2),
+        KeyName::Kp7 => Some(Key::Numpad7),
         #[cfg(target_os = "windows")]
-        "Num3" => Some(Key::Num3),
+        KeyName::Kp8 => Some(Key::Numpad8),
         #[cfg(target_os = "windows")]
-        "Num4" => Some(Key::Num4),
+        KeyName::Kp9 => Some(Key::Numpad9),
         #[cfg(target_os = "windows")]
-        "Num5" => Some(Key::Num5),
+        KeyName::KpMinus => Some(Key::Subtract),
         #[cfg(target_os = "windows")]
-        "Num6" => Some(Key::Num6),
+        KeyName::KpPlus => Some(Key::Add),
         #[cfg(target_os = "windows")]
-        "Num7" => Some(Key::Num7),
+        KeyName::KpMultiply => Some(Key::Multiply),
         #[cfg(target_os = "windows")]
-        "Num8" => Some(Key::Num8),
+        KeyName::KpDivide => Some(Key::Divide),
         #[cfg(target_os = "windows")]
-        "Num9" => Some(Key::Num9),
-        
-        "Help" => Some(Key::Help),
-        
-        _ if key_str.len() == 1 => {
-            let c = key_str.chars().next().unwrap();
-            Some(Key::Unicode(c))
-        },
-        
-        _ => None,
+        KeyName::KpDelete => Some(Key::Decimal),
+
+        // Character keys are typed as their unshifted US-layout character
+        _ => key.to_char().map(Key::Unicode),
     }
 }
 
 pub fn key_simulation_handling() {
+    key_simulation_handling_with(&Options::from_env());
+}
+
+pub fn key_simulation_handling_with(options: &Options) {
     let stdin = io::stdin();
-    let mut enigo = Enigo::new(&Settings::default()).unwrap();
-    println!("Simulation mode active. Listening for JSON input...");
-
-    for line in stdin.lock().lines() {
-        if let Ok(line) = line {
-            match serde_json::from_str::<SimulationEvent>(&line) {
-                Ok(simulation_event) => {
-                    match simulation_event {
-                        SimulationEvent::Key(key_event) => {
-                            handle_key_event(&mut enigo, key_event);
-                        },
-                        SimulationEvent::Mouse(mouse_event) => {
-                            handle_mouse_event(&mut enigo, mouse_event);
-                        },
-                        SimulationEvent::Text(text_event) => {
-                            handle_text_event(&mut enigo, text_event);
-                        }
-                    }
-                },
-                Err(e) => {
-                    eprintln!("Error parsing JSON: {}. Expected format for \nkey: {{\"event_type\": \"key\", \"key\": \"a\", \"action\": \"tap\", \"delay_after_ms\": 100}}, \nmouse: {{\"event_type\": \"mouse\", \"action\": \"move\", \"x\": 100, \"y\": 200}}, or \ntext: {{\"event_type\": \"text\", \"text\": \"hello\", \"delay_after_ms\": 50}}", e);
-                }
+    let mut backend = match EnigoBackend::new() {
+        Ok(backend) => backend,
+        Err(error) => {
+            eprintln!("Error initializing input simulation: {}", error);
+            process::exit(1);
+        }
+    };
+    let sink = open_stdout(options, &[Mode::Simulation]);
+
+    simulate_input(&mut backend, stdin.lock(), options.format);
+    sink.finish();
+}
+
+// Executes simulation commands encoded in `format` until the input ends
+pub fn simulate_input(backend: &mut dyn InputBackend, mut input: impl BufRead, format: Format) {
+    if format == Format::Jsonl {
+        simulate_lines(backend, input);
+        return;
+    }
+
+    loop {
+        match format.read_message(&mut input) {
+            Ok(Some(frame)) => match format.decode::<SimulationEvent>(&frame) {
+                Ok(simulation_event) => simulate_event(backend, simulation_event),
+                Err(error) => eprintln!("Error decoding {} command: {}", format, error),
+            },
+            Ok(None) => break,
+            Err(error) => {
+                eprintln!("Error reading {} input: {}", format, error);
+                break;
+            }
+        }
+    }
+}
+
+// Executes one JSON simulation command per line until the input ends
+pub fn simulate_lines(backend: &mut dyn InputBackend, input: impl BufRead) {
+    for line in input.lines().map_while(Result::ok) {
+        match serde_json::from_str::<SimulationEvent>(&line) {
+            Ok(simulation_event) => {
+                simulate_event(backend, simulation_event);
+            },
+            Err(e) => {
+                eprintln!("Error parsing JSON: {}. Expected format for \nkey: {{\"event_type\": \"key\", \"key\": \"a\", \"action\": \"tap\", \"delay_after_ms\": 100}}, \nmouse: {{\"event_type\": \"mouse\", \"action\": \"move\", \"x\": 100, \"y\": 200}}, or \ntext: {{\"event_type\": \"text\", \"text\": \"hello\", \"delay_after_ms\": 50}}", e);
             }
         }
     }
 }
 
-pub fn handle_key_event(enigo: &mut Enigo, event: KeySimulationEvent) {
+pub fn simulate_event(backend: &mut dyn InputBackend, event: SimulationEvent) {
+    match event {
+        SimulationEvent::Key(key_event) => {
+            handle_key_event(backend, key_event);
+        },
+        SimulationEvent::Mouse(mouse_event) => {
+            handle_mouse_event(backend, mouse_event);
+        },
+        SimulationEvent::Text(text_event) => {
+            handle_text_event(backend, text_event);
+        }
+    }
+}
+
+pub fn handle_key_event(backend: &mut dyn InputBackend, event: KeySimulationEvent) {
     match string_to_key(&event.key) {
         Some(key) => {
             match event.action.as_str() {
                 "press" => {
-                    let _ = enigo.key(key, enigo::Direction::Press);
+                    let _ = backend.key(key, enigo::Direction::Press);
                 }
                 "release" => {
-                    let _ = enigo.key(key, enigo::Direction::Release);
+                    let _ = backend.key(key, enigo::Direction::Release);
                 }
                 "tap" => {
-                    let _ = enigo.key(key, enigo::Direction::Click);
+                    let _ = backend.key(key, enigo::Direction::Click);
                 }
                 _ => {
                     eprintln!("Unknown key action: {}. Valid actions are: press, release, tap", 
@@ -174,7 +242,7 @@ pub fn handle_key_event(enigo: &mut Enigo, event: KeySimulationEvent) {
             }
 
             if let Some(delay) = event.delay_after_ms {
-                thread::sleep(Duration::from_millis(delay));
+                backend.sleep(Duration::from_millis(delay));
             }
         },
         None => eprintln!("Unsupported key: {}", event.key)
@@ -238,15 +306,15 @@ fn ease_in_out_sine(t: f64, b: f64, c: f64, d: f64) -> f64 {
 }
 // --- End Easing Functions ---
 
-pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
+pub fn handle_mouse_event(backend: &mut dyn InputBackend, event: MouseSimulationEvent) {
     match event.action.as_str() {
         "move" => {
             if let (Some(target_x), Some(target_y)) = (event.x, event.y) {
                 if let (Some(duration_ms), Some(ease_name)) = (event.duration_ms, &event.ease) {
                     if duration_ms > 0 {
                         // Animated move
-                        let start_time = Instant::now();
-                        let start_pos = enigo.location().unwrap_or((0, 0));
+                        let start_time = backend.now();
+                        let start_pos = backend.location().unwrap_or((0, 0));
                         let start_x = start_pos.0 as f64;
                         let start_y = start_pos.1 as f64;
                         let target_x_f64 = target_x as f64;
@@ -273,7 +341,7 @@ pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
                         };
 
                         loop {
-                            let elapsed = start_time.elapsed().as_millis() as f64;
+                            let elapsed = (backend.now() - start_time).as_millis() as f64;
                             if elapsed >= duration_f64 {
                                 break;
                             }
@@ -281,20 +349,20 @@ pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
                             let current_x = ease_func(elapsed, start_x, change_x, duration_f64);
                             let current_y = ease_func(elapsed, start_y, change_y, duration_f64);
 
-                            let _ = enigo.move_mouse(current_x as i32, current_y as i32, enigo::Coordinate::Abs);
+                            let _ = backend.move_mouse(current_x as i32, current_y as i32, enigo::Coordinate::Abs);
                             // Small sleep to yield control and manage update rate
-                            thread::sleep(Duration::from_millis(5));
+                            backend.sleep(Duration::from_millis(5));
                         }
                         // Ensure final position is exact
-                        let _ = enigo.move_mouse(target_x, target_y, enigo::Coordinate::Abs);
+                        let _ = backend.move_mouse(target_x, target_y, enigo::Coordinate::Abs);
 
                     } else {
                         // Instant move if duration is 0
-                        let _ = enigo.move_mouse(target_x, target_y, enigo::Coordinate::Abs);
+                        let _ = backend.move_mouse(target_x, target_y, enigo::Coordinate::Abs);
                     }
                 } else {
                     // Instant move if duration or ease is not specified
-                    let _ = enigo.move_mouse(target_x, target_y, enigo::Coordinate::Abs);
+                    let _ = backend.move_mouse(target_x, target_y, enigo::Coordinate::Abs);
                 }
             } else {
                 eprintln!("Move action requires both x and y coordinates");
@@ -310,7 +378,7 @@ pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
                     enigo::Button::Left
                 }
             };
-            let _ = enigo.button(button, enigo::Direction::Click);
+            let _ = backend.button(button, enigo::Direction::Click);
         },
         "press" => {
             let button = match event.button.as_deref() {
@@ -322,7 +390,7 @@ pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
                     enigo::Button::Left
                 }
             };
-            let _ = enigo.button(button, enigo::Direction::Press);
+            let _ = backend.button(button, enigo::Direction::Press);
         },
         "release" => {
             let button = match event.button.as_deref() {
@@ -334,7 +402,7 @@ pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
                     enigo::Button::Left
                 }
             };
-            let _ = enigo.button(button, enigo::Direction::Release);
+            let _ = backend.button(button, enigo::Direction::Release);
         },
         "scroll" => {
             let target_scroll_x = event.delta_x.unwrap_or(0);
@@ -343,7 +411,7 @@ pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
             if let (Some(duration_ms), Some(ease_name)) = (event.duration_ms, &event.ease) {
                 if duration_ms > 0 && (target_scroll_x != 0 || target_scroll_y != 0) {
                     // Animated scroll
-                    let start_time = Instant::now();
+                    let start_time = backend.now();
                     let start_scroll_x = 0.0; // Scroll is relative, so start is always 0 for the animation delta
                     let start_scroll_y = 0.0;
                     let target_scroll_x_f64 = target_scroll_x as f64;
@@ -371,7 +439,7 @@ pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
                     let mut last_scrolled_y = 0.0;
 
                     loop {
-                        let elapsed = start_time.elapsed().as_millis() as f64;
+                        let elapsed = (backend.now() - start_time).as_millis() as f64;
                         if elapsed >= duration_f64 {
                             break;
                         }
@@ -382,44 +450,45 @@ pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
                         let scroll_delta_x = current_total_scroll_x - last_scrolled_x;
                         let scroll_delta_y = current_total_scroll_y - last_scrolled_y;
 
+                        // Only whole units are scrolled; the fraction carries over
                         if scroll_delta_x.abs() >= 1.0 {
-                            let _ = enigo.scroll(scroll_delta_x as i32, enigo::Axis::Horizontal);
-                            last_scrolled_x += scroll_delta_x;
+                            let _ = backend.scroll(scroll_delta_x as i32, enigo::Axis::Horizontal);
+                            last_scrolled_x += scroll_delta_x.trunc();
                         }
                         if scroll_delta_y.abs() >= 1.0 {
-                            let _ = enigo.scroll(scroll_delta_y as i32, enigo::Axis::Vertical);
-                            last_scrolled_y += scroll_delta_y;
+                            let _ = backend.scroll(scroll_delta_y as i32, enigo::Axis::Vertical);
+                            last_scrolled_y += scroll_delta_y.trunc();
                         }
 
                         // Small sleep to yield control and manage update rate
-                        thread::sleep(Duration::from_millis(5));
+                        backend.sleep(Duration::from_millis(5));
                     }
                     // Ensure final scroll amount is exact by scrolling the remaining difference
                     let final_delta_x = target_scroll_x_f64 - last_scrolled_x;
                     let final_delta_y = target_scroll_y_f64 - last_scrolled_y;
                     if final_delta_x.abs() >= 1.0 {
-                         let _ = enigo.scroll(final_delta_x as i32, enigo::Axis::Horizontal);
+                         let _ = backend.scroll(final_delta_x as i32, enigo::Axis::Horizontal);
                     }
                      if final_delta_y.abs() >= 1.0 {
-                         let _ = enigo.scroll(final_delta_y as i32, enigo::Axis::Vertical);
+                         let _ = backend.scroll(final_delta_y as i32, enigo::Axis::Vertical);
                     }
 
                 } else {
                     // Instant scroll if duration is 0 or no scroll needed
                     if target_scroll_x != 0 {
-                        let _ = enigo.scroll(target_scroll_x, enigo::Axis::Horizontal);
+                        let _ = backend.scroll(target_scroll_x, enigo::Axis::Horizontal);
                     }
                     if target_scroll_y != 0 {
-                        let _ = enigo.scroll(target_scroll_y, enigo::Axis::Vertical);
+                        let _ = backend.scroll(target_scroll_y, enigo::Axis::Vertical);
                     }
                 }
             } else {
                  // Instant scroll if duration or ease is not specified
                  if target_scroll_x != 0 {
-                    let _ = enigo.scroll(target_scroll_x, enigo::Axis::Horizontal);
+                    let _ = backend.scroll(target_scroll_x, enigo::Axis::Horizontal);
                  }
                  if target_scroll_y != 0 {
-                    let _ = enigo.scroll(target_scroll_y, enigo::Axis::Vertical);
+                    let _ = backend.scroll(target_scroll_y, enigo::Axis::Vertical);
                  }
             }
         },
@@ -430,14 +499,14 @@ pub fn handle_mouse_event(enigo: &mut Enigo, event: MouseSimulationEvent) {
     }
 
     if let Some(delay) = event.delay_after_ms {
-        thread::sleep(Duration::from_millis(delay));
+        backend.sleep(Duration::from_millis(delay));
     }
 }
 
-pub fn handle_text_event(enigo: &mut Enigo, event: TextSimulationEvent) {
-    let _ = enigo.text(&event.text);
+pub fn handle_text_event(backend: &mut dyn InputBackend, event: TextSimulationEvent) {
+    let _ = backend.text(&event.text);
 
     if let Some(delay) = event.delay_after_ms {
-        thread::sleep(Duration::from_millis(delay));
+        backend.sleep(Duration::from_millis(delay));
     }
 }
\ No newline at end of file
@@ -0,0 +1,221 @@
+use crate::clock::{with_time_format, TimeFormat};
+use crate::format::Format;
+use crate::models::{OutputEvent, Record, Summary};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long the final summary waits for a write in progress before giving up on the sink
+const FINAL_SUMMARY_WAIT: Duration = Duration::from_secs(1);
+
+/// Destination for the records produced by the listener modes.
+pub trait EventSink {
+    fn emit(&mut self, record: Record) -> io::Result<()>;
+}
+
+/// Writes records to standard output, JSON lines unless another format is chosen.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdoutSink {
+    format: Format,
+    time_format: TimeFormat,
+}
+
+impl StdoutSink {
+    pub fn new(format: Format) -> Self {
+        StdoutSink { format, time_format: TimeFormat::default() }
+    }
+
+    pub fn with_time_format(mut self, time_format: TimeFormat) -> Self {
+        self.time_format = time_format;
+        self
+    }
+}
+
+impl EventSink for StdoutSink {
+    fn emit(&mut self, record: Record) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        with_time_format(self.time_format, || self.format.write(&mut stdout, &record))?;
+        stdout.flush()
+    }
+}
+
+/// Writes records to a file, flushing after every record.
+#[derive(Debug)]
+pub struct FileSink {
+    writer: BufWriter<File>,
+    format: Format,
+    time_format: TimeFormat,
+}
+
+impl FileSink {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::create_with_format(path, Format::Jsonl)
+    }
+
+    pub fn create_with_format(path: impl AsRef<Path>, format: Format) -> io::Result<Self> {
+        Ok(FileSink { writer: BufWriter::new(File::create(path)?), format, time_format: TimeFormat::default() })
+    }
+
+    pub fn with_time_format(mut self, time_format: TimeFormat) -> Self {
+        self.time_format = time_format;
+        self
+    }
+}
+
+impl EventSink for FileSink {
+    fn emit(&mut self, record: Record) -> io::Result<()> {
+        with_time_format(self.time_format, || self.format.write(&mut self.writer, &record))?;
+        self.writer.flush()
+    }
+}
+
+/// Collects records in memory. Clones share the same buffer, so keep one
+/// around to inspect what a handler emitted after handing the other to `run`.
+#[derive(Debug, Default, Clone)]
+pub struct MemorySink {
+    records: Arc<Mutex<Vec<Record>>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn records(&self) -> Vec<Record> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn take(&self) -> Vec<Record> {
+        std::mem::take(&mut *self.records.lock().unwrap())
+    }
+}
+
+impl EventSink for MemorySink {
+    fn emit(&mut self, record: Record) -> io::Result<()> {
+        self.records.lock().unwrap().push(record);
+        Ok(())
+    }
+}
+
+/// Forwards records over an mpsc channel.
+#[derive(Debug, Clone)]
+pub struct ChannelSink {
+    sender: Sender<Record>,
+}
+
+impl ChannelSink {
+    pub fn new(sender: Sender<Record>) -> Self {
+        ChannelSink { sender }
+    }
+}
+
+impl EventSink for ChannelSink {
+    fn emit(&mut self, record: Record) -> io::Result<()> {
+        self.sender
+            .send(record)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "record receiver was dropped"))
+    }
+}
+
+struct Sequenced {
+    sink: Box<dyn EventSink + Send>,
+    next_seq: u64,
+}
+
+/// Numbers every record passing through it and counts how many were written
+/// and how many failed, so consumers can spot gaps in `seq`. Summary records
+/// are numbered but not counted.
+///
+/// Clones share the same counter and inner sink, so a summary thread can run
+/// alongside the listener.
+#[derive(Clone)]
+pub struct SequencedSink {
+    inner: Arc<Mutex<Sequenced>>,
+    emitted: Arc<AtomicU64>,
+    failed: Arc<AtomicU64>,
+}
+
+impl SequencedSink {
+    pub fn new(sink: impl EventSink + Send + 'static) -> Self {
+        SequencedSink {
+            inner: Arc::new(Mutex::new(Sequenced { sink: Box::new(sink), next_seq: 0 })),
+            emitted: Arc::default(),
+            failed: Arc::default(),
+        }
+    }
+
+    pub fn emitted(&self) -> u64 {
+        self.emitted.load(Ordering::SeqCst)
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::SeqCst)
+    }
+
+    fn emit_locked(&self, inner: &mut Sequenced, mut record: Record) -> io::Result<()> {
+        record.seq = Some(inner.next_seq);
+        inner.next_seq += 1;
+        let is_summary = matches!(record.event, OutputEvent::Summary(_));
+        let result = inner.sink.emit(record);
+        if !is_summary {
+            match result {
+                Ok(()) => self.emitted.fetch_add(1, Ordering::SeqCst),
+                Err(_) => self.failed.fetch_add(1, Ordering::SeqCst),
+            };
+        }
+        result
+    }
+
+    fn summary(&self, is_final: bool) -> Record {
+        OutputEvent::from(Summary {
+            emitted: self.emitted(),
+            failed: self.failed(),
+            is_final,
+        })
+        .into()
+    }
+
+    /// Writes a summary record with the counts so far.
+    pub fn emit_summary(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let summary = self.summary(false);
+        if let Err(error) = self.emit_locked(&mut inner, summary) {
+            eprintln!("Error writing summary: {}", error);
+        }
+    }
+
+    /// Writes the closing summary. Falls back to stderr if the sink cannot
+    /// take it, e.g. because a write has been stuck on a full pipe.
+    pub fn finish(&self) {
+        let deadline = Instant::now() + FINAL_SUMMARY_WAIT;
+        let result = loop {
+            match self.inner.try_lock() {
+                Ok(mut inner) => {
+                    let summary = self.summary(true);
+                    break self.emit_locked(&mut inner, summary);
+                }
+                Err(TryLockError::Poisoned(poisoned)) => {
+                    let summary = self.summary(true);
+                    break self.emit_locked(&mut poisoned.into_inner(), summary);
+                }
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                Err(TryLockError::WouldBlock) => break Err(io::Error::new(io::ErrorKind::WouldBlock, "output is stalled")),
+            }
+        };
+        if let Err(error) = result {
+            eprintln!("Error writing summary ({}): emitted {}, failed {}", error, self.emitted(), self.failed());
+        }
+    }
+}
+
+impl EventSink for SequencedSink {
+    fn emit(&mut self, record: Record) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.emit_locked(&mut inner, record)
+    }
+}
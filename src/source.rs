@@ -0,0 +1,103 @@
+use rdev::{listen, Event, ListenError};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Error returned when an event source fails to start or read its input.
+#[derive(Debug)]
+pub enum SourceError {
+    Listen(ListenError),
+    Io(io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::Listen(error) => write!(f, "failed to listen for input events: {:?}", error),
+            SourceError::Io(error) => write!(f, "failed to read events: {}", error),
+            SourceError::Parse(error) => write!(f, "failed to parse event: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+impl From<io::Error> for SourceError {
+    fn from(error: io::Error) -> Self {
+        SourceError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SourceError {
+    fn from(error: serde_json::Error) -> Self {
+        SourceError::Parse(error)
+    }
+}
+
+/// Something that produces `rdev` events and feeds them to a callback.
+///
+/// `listen` blocks until the source is exhausted. The OS hook never is.
+pub trait EventSource {
+    fn listen(&mut self, callback: Box<dyn FnMut(Event)>) -> Result<(), SourceError>;
+
+    /// Whether events arrive as they happen. Sources that replay recorded
+    /// events say no, and are timed by the times their events carry instead.
+    fn is_live(&self) -> bool {
+        true
+    }
+}
+
+/// Live events from the global OS hook installed by `rdev::listen`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RdevSource;
+
+impl EventSource for RdevSource {
+    fn listen(&mut self, callback: Box<dyn FnMut(Event)>) -> Result<(), SourceError> {
+        listen(callback).map_err(SourceError::Listen)
+    }
+}
+
+/// Replays a scripted list of events, in order, then returns.
+#[derive(Debug, Default, Clone)]
+pub struct ReplaySource {
+    events: Vec<Event>,
+}
+
+impl ReplaySource {
+    pub fn new(events: Vec<Event>) -> Self {
+        ReplaySource { events }
+    }
+
+    /// Reads one serialized `rdev::Event` per line, skipping blank lines.
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, SourceError> {
+        let mut events = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            events.push(serde_json::from_str::<Event>(&line)?);
+        }
+        Ok(ReplaySource { events })
+    }
+
+    pub fn from_jsonl(path: impl AsRef<Path>) -> Result<Self, SourceError> {
+        let file = File::open(path)?;
+        Self::from_reader(BufReader::new(file))
+    }
+}
+
+impl EventSource for ReplaySource {
+    fn listen(&mut self, mut callback: Box<dyn FnMut(Event)>) -> Result<(), SourceError> {
+        for event in self.events.drain(..) {
+            callback(event);
+        }
+        Ok(())
+    }
+
+    fn is_live(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,180 @@
+use rdev::{Event, EventType, Key};
+use crate::clock::{self, EventTime};
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::keys::{event_text, KeyName};
+use crate::mode::Mode;
+use crate::models::{IntervalBucket, KeyStats, OutputEvent, StatsEvent};
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// How often statistics are written unless `--stats-interval` says otherwise.
+pub const DEFAULT_STATS_INTERVAL: Duration = Duration::from_secs(60);
+
+// A pause between presses at least this long is not counted as typing time
+const IDLE_GAP: Duration = Duration::from_secs(5);
+
+// Upper bounds of the inter-key interval histogram buckets, in milliseconds
+const INTERVAL_BOUNDS_MS: [u64; 8] = [50, 100, 150, 200, 300, 500, 1000, 2000];
+
+// Characters per word for words per minute
+const CHARS_PER_WORD: f64 = 5.0;
+
+#[derive(Debug, Default)]
+struct KeyTotals {
+    presses: u64,
+    releases: u64, // Presses whose hold time is known
+    hold_total: Duration,
+}
+
+// Counts presses and hold times with the same press/release tracking as
+// hold_and_release mode, and reports the totals so far every interval and
+// once more when the stream ends
+#[derive(Debug, Default)]
+pub struct StatsHandler {
+    interval: Option<Duration>,
+    started: Duration,
+    deadline: Option<Duration>,
+    pressed_keys: HashMap<Key, Duration>, // Keys down and when they went down
+    keys: HashMap<KeyName, KeyTotals>,
+    presses: u64,
+    backspaces: u64,
+    last_press: Option<Duration>,
+    active: Duration,
+    active_presses: u64, // Presses that ended an interval counted in `active`, for the rates
+    active_text_presses: u64,
+    intervals: [u64; INTERVAL_BOUNDS_MS.len() + 1],
+}
+
+impl StatsHandler {
+    // Statistics every `interval`, or only at the end for `None` or zero
+    pub fn new(interval: Option<Duration>) -> Self {
+        let interval = interval.filter(|interval| !interval.is_zero());
+        let started = clock::monotonic();
+        StatsHandler { interval, started, deadline: interval.map(|interval| started + interval), ..Self::default() }
+    }
+
+    fn press(&mut self, event: &Event, key: Key, now: Duration) {
+        // Auto-repeat
+        if self.pressed_keys.contains_key(&key) {
+            return;
+        }
+        self.pressed_keys.insert(key, now);
+
+        self.presses += 1;
+        self.keys.entry(key.into()).or_default().presses += 1;
+        if key == Key::Backspace {
+            self.backspaces += 1;
+        }
+
+        if let Some(last_press) = self.last_press.replace(now) {
+            let interval = now.saturating_sub(last_press);
+            if interval < IDLE_GAP {
+                self.active += interval;
+                self.active_presses += 1;
+                if event_text(event).is_some_and(|text| !text.chars().any(char::is_control)) {
+                    self.active_text_presses += 1;
+                }
+            }
+            let millis = interval.as_millis() as u64;
+            let bucket = INTERVAL_BOUNDS_MS.iter().position(|bound| millis < *bound).unwrap_or(INTERVAL_BOUNDS_MS.len());
+            self.intervals[bucket] += 1;
+        }
+    }
+
+    fn release(&mut self, key: Key, now: Duration) {
+        if let Some(pressed_at) = self.pressed_keys.remove(&key) {
+            let totals = self.keys.entry(key.into()).or_default();
+            totals.releases += 1;
+            totals.hold_total += now.saturating_sub(pressed_at);
+        }
+    }
+
+    fn stats(&self, now: Duration, is_final: bool) -> StatsEvent {
+        let active_minutes = self.active.as_secs_f64() / 60.0;
+        let per_minute = |count: f64| if active_minutes > 0.0 { round(count / active_minutes) } else { 0.0 };
+        let keys = self
+            .keys
+            .iter()
+            .map(|(key, totals)| {
+                let avg_hold_ms = (totals.releases > 0).then(|| round(totals.hold_total.as_secs_f64() * 1000.0 / totals.releases as f64));
+                (key.to_string(), KeyStats { presses: totals.presses, avg_hold_ms })
+            })
+            .collect::<BTreeMap<_, _>>();
+        let intervals = self
+            .intervals
+            .iter()
+            .enumerate()
+            .map(|(index, count)| IntervalBucket { below_ms: INTERVAL_BOUNDS_MS.get(index).copied(), count: *count })
+            .collect();
+
+        StatsEvent {
+            elapsed_ms: now.saturating_sub(self.started).as_millis() as u64,
+            active_ms: self.active.as_millis() as u64,
+            presses: self.presses,
+            keys_per_minute: per_minute(self.active_presses as f64),
+            words_per_minute: per_minute(self.active_text_presses as f64 / CHARS_PER_WORD),
+            backspace_ratio: if self.presses > 0 { round(self.backspaces as f64 / self.presses as f64) } else { 0.0 },
+            keys,
+            intervals,
+            is_final,
+            time: EventTime::at(now),
+        }
+    }
+}
+
+// Two decimals are plenty for rates and averages
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+impl Handler for StatsHandler {
+    fn handle(&mut self, event: &Event, _out: &mut Vec<OutputEvent>) {
+        let now = Duration::from_micros(EventTime::of(event).monotonic_us);
+        match event.event_type {
+            EventType::KeyPress(key) => self.press(event, key, now),
+            EventType::KeyRelease(key) => self.release(key, now),
+            _ => {} // Ignore other event types
+        }
+    }
+
+    fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    // Every interval that passed is reported, not only the latest
+    fn expire(&mut self, now: Duration, out: &mut Vec<OutputEvent>) {
+        while let Some(deadline) = self.deadline.filter(|deadline| *deadline <= now) {
+            out.push(self.stats(deadline, false).into());
+            self.deadline = self.interval.map(|interval| deadline + interval);
+        }
+    }
+
+    fn finish(&mut self, out: &mut Vec<OutputEvent>) {
+        out.push(self.stats(Duration::from_micros(EventTime::now().monotonic_us), true).into());
+    }
+}
+
+// `--stats-interval 0` turns the periodic statistics off
+pub fn stats_interval(options: &Options) -> Option<Duration> {
+    match options.stats_interval {
+        Some(interval) if interval.is_zero() => None,
+        Some(interval) => Some(interval),
+        None => Some(DEFAULT_STATS_INTERVAL),
+    }
+}
+
+pub fn stats_handling() {
+    stats_handling_with(&Options::from_env());
+}
+
+pub fn stats_handling_with(options: &Options) {
+    let sink = open_stdout(options, &[Mode::Stats]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(StatsHandler::new(stats_interval(options)), options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for keyboard events: {}", error);
+    }
+    sink.finish();
+}
@@ -0,0 +1,152 @@
+use rdev::{Event, EventType, Key};
+use crate::clock::EventTime;
+use crate::filter::Filtered;
+use crate::handler::{run, Handler};
+use crate::keys::event_text;
+use crate::mode::Mode;
+use crate::models::{CommitReason, OutputEvent, TextCommitEvent};
+use crate::modifiers::ModifierTracker;
+use crate::options::Options;
+use crate::output::open_stdout;
+use crate::source::RdevSource;
+use std::time::Duration;
+
+/// How long typing may pause before the buffer is committed, unless
+/// `--text-idle` says otherwise.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_millis(2000);
+
+// The line being typed and the caret within it
+#[derive(Debug, Default)]
+struct LineBuffer {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl LineBuffer {
+    fn insert(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.chars.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn take(&mut self) -> String {
+        self.cursor = 0;
+        self.chars.drain(..).collect()
+    }
+}
+
+// Replays typing into a line buffer and commits it when the user finishes the
+// line or seems to have moved elsewhere. Keys whose effect cannot be known
+// from the key stream, such as shortcuts or selections, commit what was typed
+// so far rather than let the buffer drift from the real text.
+#[derive(Debug, Default)]
+pub struct TextHandler {
+    idle_timeout: Duration,
+    modifiers: ModifierTracker,
+    buffer: LineBuffer,
+    deadline: Option<Duration>,
+}
+
+impl TextHandler {
+    pub fn new(idle_timeout: Duration) -> Self {
+        TextHandler { idle_timeout, ..Self::default() }
+    }
+
+    // Empty buffers are not reported
+    fn commit(&mut self, reason: CommitReason, time: EventTime, out: &mut Vec<OutputEvent>) {
+        self.deadline = None;
+        let text = self.buffer.take();
+        if !text.is_empty() {
+            out.push(TextCommitEvent { text, reason, time }.into());
+        }
+    }
+
+    fn press(&mut self, event: &Event, key: Key, time: EventTime, out: &mut Vec<OutputEvent>) {
+        let modifiers = self.modifiers.current();
+        let buffer = &mut self.buffer;
+        match key {
+            // Modifiers and locks only change what the next key types
+            Key::ShiftLeft | Key::ShiftRight | Key::ControlLeft | Key::ControlRight | Key::Alt | Key::AltGr
+            | Key::MetaLeft | Key::MetaRight | Key::CapsLock | Key::NumLock => return,
+            // AltGr arrives as Ctrl+Alt on Windows but types characters
+            _ if (modifiers.ctrl || modifiers.alt || modifiers.meta) && !modifiers.alt_gr => {
+                return self.commit(CommitReason::Shortcut, time, out);
+            }
+            Key::Return | Key::KpReturn => return self.commit(CommitReason::Return, time, out),
+            Key::Tab => return self.commit(CommitReason::Tab, time, out),
+            Key::UpArrow | Key::DownArrow | Key::PageUp | Key::PageDown | Key::Escape => {
+                return self.commit(CommitReason::Navigation, time, out);
+            }
+            Key::LeftArrow | Key::RightArrow | Key::Home | Key::End if modifiers.shift => {
+                return self.commit(CommitReason::Navigation, time, out);
+            }
+            Key::LeftArrow if buffer.cursor == 0 => return self.commit(CommitReason::Navigation, time, out),
+            Key::LeftArrow => buffer.cursor -= 1,
+            Key::RightArrow => buffer.cursor = (buffer.cursor + 1).min(buffer.chars.len()),
+            Key::Home => buffer.cursor = 0,
+            Key::End => buffer.cursor = buffer.chars.len(),
+            Key::Backspace => buffer.backspace(),
+            Key::Delete | Key::KpDelete => buffer.delete(),
+            _ => match event_text(event) {
+                Some(text) => buffer.insert(&text),
+                None => return, // Keys that type nothing do not count as typing
+            },
+        }
+        self.deadline = Some(Duration::from_micros(time.monotonic_us) + self.idle_timeout);
+    }
+}
+
+impl Handler for TextHandler {
+    fn handle(&mut self, event: &Event, out: &mut Vec<OutputEvent>) {
+        self.modifiers.update(event);
+        let time = EventTime::of(event);
+        match event.event_type {
+            EventType::KeyPress(key) => self.press(event, key, time, out),
+            EventType::ButtonPress(_) => self.commit(CommitReason::Click, time, out),
+            _ => {} // Ignore other event types
+        }
+    }
+
+    fn observe(&mut self, event: &Event) {
+        self.modifiers.update(event);
+    }
+
+    fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    fn expire(&mut self, now: Duration, out: &mut Vec<OutputEvent>) {
+        self.commit(CommitReason::Idle, EventTime::at(self.deadline.unwrap_or(now)), out);
+    }
+
+    fn finish(&mut self, out: &mut Vec<OutputEvent>) {
+        self.commit(CommitReason::End, EventTime::now(), out);
+    }
+}
+
+pub fn text_handling() {
+    text_handling_with(&Options::from_env());
+}
+
+pub fn text_handling_with(options: &Options) {
+    let handler = TextHandler::new(options.text_idle.unwrap_or(DEFAULT_IDLE_TIMEOUT));
+    let sink = open_stdout(options, &[Mode::Text]);
+    if let Err(error) = run(&mut RdevSource, Filtered::new(handler, options.filter.clone()), sink.clone()) {
+        eprintln!("Error listening for input events: {}", error);
+    }
+    sink.finish();
+}
@@ -0,0 +1,36 @@
+use key_listener::clock::EventClock;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[test]
+fn live_readings_keep_going_when_the_wall_clock_is_set_back() {
+    let mut clock = EventClock::live();
+    let now = SystemTime::now();
+    let first = clock.reading(now);
+    thread::sleep(Duration::from_millis(5));
+    let second = clock.reading(now - Duration::from_secs(3600));
+    assert!(second >= first + Duration::from_millis(5), "{:?} after {:?}", second, first);
+    thread::sleep(Duration::from_millis(5));
+    let third = clock.reading(now - Duration::from_secs(3600) + Duration::from_millis(5));
+    assert!(third >= second + Duration::from_millis(5), "{:?} after {:?}", third, second);
+}
+
+#[test]
+fn live_readings_do_not_jump_when_the_wall_clock_is_set_forward() {
+    let mut clock = EventClock::live();
+    let now = SystemTime::now();
+    let first = clock.reading(now);
+    let second = clock.reading(now + Duration::from_secs(3600));
+    assert!(second - first < Duration::from_secs(1), "{:?} after {:?}", second, first);
+}
+
+#[test]
+fn replays_keep_the_recorded_spacing() {
+    let mut clock = EventClock::replay();
+    let first = clock.reading(UNIX_EPOCH + Duration::from_millis(10_000));
+    let second = clock.reading(UNIX_EPOCH + Duration::from_millis(10_250));
+    assert_eq!(second - first, Duration::from_millis(250));
+    assert_eq!(clock.reading(UNIX_EPOCH + Duration::from_millis(10_100)), second, "an earlier time holds the reading");
+    assert_eq!(clock.now(), second);
+    assert_eq!(clock.wall_time(second + Duration::from_secs(1)), UNIX_EPOCH + Duration::from_millis(11_250));
+}
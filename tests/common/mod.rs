@@ -0,0 +1,44 @@
+// Helpers for the handler tests, which drive a mode with scripted events
+// through `ReplaySource` and read back what it wrote to a `MemorySink`. Each
+// test file uses only some of them.
+#![allow(dead_code)]
+
+use key_listener::handler::{run, Handler};
+use key_listener::sink::MemorySink;
+use key_listener::source::ReplaySource;
+use rdev::{Event, EventType, Key};
+use serde_json::Value;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Epoch milliseconds of the first scripted event, as written in `timestamp`.
+pub const T0: u64 = 1_600_000_000_000;
+
+pub fn press(ms: u64, key: Key) -> Event {
+    Event { time: UNIX_EPOCH + Duration::from_millis(T0 + ms), name: None, event_type: EventType::KeyPress(key) }
+}
+
+pub fn release(ms: u64, key: Key) -> Event {
+    Event { time: UNIX_EPOCH + Duration::from_millis(T0 + ms), name: None, event_type: EventType::KeyRelease(key) }
+}
+
+/// Replays `events` through `handler` and returns the output events as JSON,
+/// without the fields that depend on the platform or on when the test runs.
+pub fn replay(handler: impl Handler + Send + 'static, events: Vec<Event>) -> Vec<Value> {
+    replay_source(handler, ReplaySource::new(events))
+}
+
+/// Like `replay`, for events read from a recording.
+pub fn replay_source(handler: impl Handler + Send + 'static, mut source: ReplaySource) -> Vec<Value> {
+    let sink = MemorySink::new();
+    run(&mut source, handler, sink.clone()).unwrap();
+    sink.records()
+        .into_iter()
+        .map(|record| {
+            let mut event = serde_json::to_value(record.event).unwrap();
+            for field in ["monotonic_us", "scan_code", "raw_code"] {
+                event.as_object_mut().unwrap().remove(field);
+            }
+            event
+        })
+        .collect()
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "complex")]
+
+mod common;
+
+use common::{press, release, replay, T0};
+use key_listener::complex::ComplexHandler;
+use rdev::Key;
+use serde_json::json;
+
+#[test]
+fn keys_join_and_leave_the_combination() {
+    let events = vec![press(0, Key::ControlLeft), press(50, Key::KeyC), release(120, Key::KeyC), release(200, Key::ControlLeft)];
+    let output: Vec<_> = replay(ComplexHandler::default(), events)
+        .into_iter()
+        .map(|mut event| {
+            event.as_object_mut().unwrap().remove("modifiers");
+            event
+        })
+        .collect();
+    assert_eq!(
+        output,
+        vec![
+            json!({"event_type": "key", "key": "ControlLeft", "pressed": true, "timestamp": T0}),
+            json!({"event_type": "combination_press", "keys": ["ControlLeft"], "timestamp": T0}),
+            json!({"event_type": "key", "key": "KeyC", "pressed": true, "timestamp": T0 + 50}),
+            json!({"event_type": "combination", "keys": ["ControlLeft", "KeyC"], "timestamp": T0 + 50}),
+            json!({"event_type": "key", "key": "KeyC", "pressed": false, "timestamp": T0 + 120}),
+            json!({"event_type": "combination", "keys": ["ControlLeft"], "timestamp": T0 + 120}),
+            json!({"event_type": "key", "key": "ControlLeft", "pressed": false, "timestamp": T0 + 200}),
+            json!({"event_type": "combination_release", "keys": ["ControlLeft"], "timestamp": T0 + 200}),
+        ]
+    );
+}
+
+#[test]
+fn auto_repeat_and_stray_releases_are_ignored() {
+    let events = vec![release(0, Key::ShiftLeft), press(10, Key::KeyA), press(510, Key::KeyA), release(540, Key::KeyA)];
+    let types: Vec<_> = replay(ComplexHandler::default(), events).iter().map(|event| event["event_type"].clone()).collect();
+    assert_eq!(types, vec![json!("key"), json!("combination_press"), json!("key"), json!("combination_release")]);
+}
+
+#[test]
+fn the_last_release_reports_the_combination_it_ends() {
+    let events = vec![press(0, Key::ShiftLeft), press(20, Key::KeyA), release(60, Key::ShiftLeft), release(90, Key::KeyA)];
+    let combinations: Vec<_> = replay(ComplexHandler::default(), events)
+        .into_iter()
+        .filter(|event| event["event_type"] != "key")
+        .map(|event| (event["event_type"].clone(), event["keys"].clone()))
+        .collect();
+    assert_eq!(
+        combinations,
+        vec![
+            (json!("combination_press"), json!(["ShiftLeft"])),
+            (json!("combination"), json!(["ShiftLeft", "KeyA"])),
+            (json!("combination"), json!(["KeyA"])),
+            (json!("combination_release"), json!(["KeyA"])),
+        ]
+    );
+}
+
+
+#[test]
+fn key_events_carry_the_modifiers_after_them() {
+    let events = vec![press(0, Key::ControlLeft), press(50, Key::KeyC), release(120, Key::KeyC), release(200, Key::ControlLeft)];
+    let ctrl = json!({"ctrl": true, "ctrl_left": true});
+    let modifiers: Vec<_> = replay(ComplexHandler::default(), events)
+        .into_iter()
+        .filter(|event| event["event_type"] == "key")
+        .map(|event| event["modifiers"].clone())
+        .collect();
+    assert_eq!(modifiers, vec![ctrl.clone(), ctrl.clone(), ctrl, json!({})]);
+}
+
+#[test]
+fn either_alt_key_sets_alt() {
+    let events = vec![press(0, Key::Alt), release(40, Key::Alt), press(100, Key::AltGr)];
+    let modifiers: Vec<_> = replay(ComplexHandler::default(), events)
+        .into_iter()
+        .filter(|event| event["event_type"] == "key")
+        .map(|event| event["modifiers"].clone())
+        .collect();
+    assert_eq!(
+        modifiers,
+        vec![json!({"alt": true, "alt_left": true}), json!({}), json!({"alt": true, "alt_right": true, "alt_gr": true})]
+    );
+}
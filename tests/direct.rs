@@ -0,0 +1,42 @@
+#![cfg(feature = "direct")]
+
+mod common;
+
+use common::{press, release, replay, T0};
+use key_listener::direct::DirectHandler;
+use rdev::Key;
+use serde_json::json;
+
+#[test]
+fn auto_repeat_presses_are_marked() {
+    let events = vec![press(0, Key::KeyA), press(500, Key::KeyA), press(533, Key::KeyA), release(560, Key::KeyA)];
+    assert_eq!(
+        replay(DirectHandler::new(false), events),
+        vec![
+            json!({"event_type": "key", "key": "KeyA", "pressed": true, "modifiers": {}, "timestamp": T0}),
+            json!({"event_type": "key", "key": "KeyA", "pressed": true, "repeat": true, "repeat_count": 1, "modifiers": {}, "timestamp": T0 + 500}),
+            json!({"event_type": "key", "key": "KeyA", "pressed": true, "repeat": true, "repeat_count": 2, "modifiers": {}, "timestamp": T0 + 533}),
+            json!({"event_type": "key", "key": "KeyA", "pressed": false, "repeat_count": 2, "modifiers": {}, "timestamp": T0 + 560}),
+        ]
+    );
+}
+
+#[test]
+fn suppressed_repeats_still_count_on_release() {
+    let events = vec![press(0, Key::KeyA), press(500, Key::KeyA), press(533, Key::KeyA), release(560, Key::KeyA)];
+    assert_eq!(
+        replay(DirectHandler::new(true), events),
+        vec![
+            json!({"event_type": "key", "key": "KeyA", "pressed": true, "modifiers": {}, "timestamp": T0}),
+            json!({"event_type": "key", "key": "KeyA", "pressed": false, "repeat_count": 2, "modifiers": {}, "timestamp": T0 + 560}),
+        ]
+    );
+}
+
+#[test]
+fn a_new_press_after_release_is_not_a_repeat() {
+    let events = vec![press(0, Key::KeyA), release(80, Key::KeyA), press(200, Key::KeyA)];
+    let output = replay(DirectHandler::new(false), events);
+    assert_eq!(output.len(), 3);
+    assert!(output.iter().all(|event| event.get("repeat").is_none() && event.get("repeat_count").is_none()));
+}
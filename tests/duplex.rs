@@ -0,0 +1,60 @@
+#![cfg(feature = "duplex")]
+
+use enigo::{Direction, Key};
+use key_listener::backend::{InputBackend, RecordingBackend};
+use key_listener::duplex::{run_duplex, InjectionTracker, TrackingBackend};
+use key_listener::filter::EventFilter;
+use key_listener::keys::KeyName;
+use key_listener::sink::MemorySink;
+use key_listener::source::ReplaySource;
+use rdev::{Event, EventType};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+fn event(event_type: EventType) -> Event {
+    Event { time: SystemTime::now(), name: None, event_type }
+}
+
+#[test]
+fn only_the_injected_key_counts_as_injected() {
+    let tracker = Arc::new(Mutex::new(InjectionTracker::default()));
+    let mut backend = TrackingBackend::new(RecordingBackend::new(), Arc::clone(&tracker));
+    backend.key(Key::Return, Direction::Click).unwrap();
+
+    let mut tracker = tracker.lock().unwrap();
+    assert!(!tracker.is_injected(&event(EventType::KeyPress(rdev::Key::KeyA))));
+    assert!(tracker.is_injected(&event(EventType::KeyPress(rdev::Key::Return))));
+    assert!(!tracker.is_injected(&event(EventType::KeyPress(rdev::Key::Return))), "each injection is consumed once");
+    assert!(!tracker.is_injected(&event(EventType::KeyRelease(rdev::Key::KeyA))));
+    assert!(tracker.is_injected(&event(EventType::KeyRelease(rdev::Key::Return))));
+}
+
+#[test]
+fn characters_match_any_key() {
+    let tracker = Arc::new(Mutex::new(InjectionTracker::default()));
+    let mut backend = TrackingBackend::new(RecordingBackend::new(), Arc::clone(&tracker));
+    backend.key(Key::Unicode('a'), Direction::Press).unwrap();
+
+    let mut tracker = tracker.lock().unwrap();
+    assert!(!tracker.is_injected(&event(EventType::KeyRelease(rdev::Key::KeyQ))));
+    assert!(tracker.is_injected(&event(EventType::KeyPress(rdev::Key::KeyQ))));
+}
+
+#[test]
+fn a_media_key_does_not_mark_the_next_physical_key() {
+    let tracker = Arc::new(Mutex::new(InjectionTracker::default()));
+    let mut backend = TrackingBackend::new(RecordingBackend::new(), Arc::clone(&tracker));
+    backend.key(Key::VolumeUp, Direction::Click).unwrap();
+
+    let volume_up = KeyName::VolumeUp.to_rdev().unwrap();
+    let events = vec![
+        event(EventType::KeyPress(rdev::Key::KeyQ)),
+        event(EventType::KeyPress(volume_up)),
+        event(EventType::KeyRelease(volume_up)),
+        event(EventType::KeyRelease(rdev::Key::KeyQ)),
+    ];
+    let sink = MemorySink::new();
+    run_duplex(&mut ReplaySource::new(events), tracker, false, EventFilter::default(), sink.clone()).unwrap();
+    let injected: Vec<bool> = sink.records().iter().map(|record| record.injected).collect();
+    assert_eq!(injected, vec![false, true, true, false]);
+}
@@ -0,0 +1,71 @@
+use key_listener::filter::{EventFilter, KeyClass, KeyPattern};
+use key_listener::keys::KeyName;
+use rdev::{Button, Event, EventType, Key};
+use std::time::SystemTime;
+
+fn patterns(patterns: &[&str]) -> Vec<KeyPattern> {
+    patterns.iter().map(|pattern| pattern.parse().unwrap()).collect()
+}
+
+fn event(event_type: EventType) -> Event {
+    Event { time: SystemTime::now(), name: None, event_type }
+}
+
+#[test]
+fn globs_match_whole_key_names_ignoring_case() {
+    let [kp, f1x, any_arrow] = ["Kp*", "f1?", "*arrow"].map(|glob| glob.parse::<KeyPattern>().unwrap());
+    assert!(kp.matches(KeyName::Kp5) && kp.matches(KeyName::KpReturn));
+    assert!(!kp.matches(KeyName::KeyP));
+    assert!(f1x.matches(KeyName::F12) && f1x.matches(KeyName::F19));
+    assert!(!f1x.matches(KeyName::F1) && !f1x.matches(KeyName::F2));
+    assert!(any_arrow.matches(KeyName::LeftArrow) && !any_arrow.matches(KeyName::Return));
+    assert!("*e*a*".parse::<KeyPattern>().unwrap().matches(KeyName::MediaPlayPause));
+    assert!(!"*a*a*".parse::<KeyPattern>().unwrap().matches(KeyName::VolumeMute));
+}
+
+#[test]
+fn classes_match_their_keys() {
+    let modifiers: KeyPattern = "@modifier".parse().unwrap();
+    assert!(modifiers.matches(KeyName::AltGr) && modifiers.matches(KeyName::ShiftRight));
+    assert!(!modifiers.matches(KeyName::KeyA));
+    let keypad: KeyPattern = "@keypad".parse().unwrap();
+    assert!(keypad.matches(KeyName::KpMultiply) && !keypad.matches(KeyName::Num1));
+    assert_eq!("@media".parse::<KeyPattern>(), Ok(KeyPattern::Class(KeyClass::Media)));
+}
+
+#[test]
+fn class_names_accept_one_plural_s() {
+    assert_eq!("modifiers".parse(), Ok(KeyClass::Modifier));
+    assert_eq!("Keypads".parse(), Ok(KeyClass::Keypad));
+    assert_eq!("media".parse(), Ok(KeyClass::Media));
+    assert!("modifierss".parse::<KeyClass>().is_err());
+}
+
+#[test]
+fn excludes_apply_after_includes() {
+    let filter = EventFilter { include_keys: Some(patterns(&["@alphanumeric", "Space"])), exclude_keys: patterns(&["Num?", "KeyQ"]), ..EventFilter::default() };
+    assert!(filter.allows_key(KeyName::KeyA));
+    assert!(filter.allows_key(KeyName::Space));
+    assert!(!filter.allows_key(KeyName::KeyQ));
+    assert!(!filter.allows_key(KeyName::Num4));
+    assert!(!filter.allows_key(KeyName::Return), "not included");
+
+    let exclude_only = EventFilter { exclude_keys: patterns(&["@modifiers"]), ..EventFilter::default() };
+    assert!(exclude_only.allows_key(KeyName::Return));
+    assert!(!exclude_only.allows_key(KeyName::ControlLeft));
+
+    let nothing = EventFilter { include_keys: Some(Vec::new()), ..EventFilter::default() };
+    assert!(!nothing.allows_key(KeyName::KeyA), "an empty include list lets nothing through");
+}
+
+#[test]
+fn buttons_are_filtered_by_name() {
+    let filter = EventFilter { include_buttons: Some(vec!["left".to_string(), "button_*".to_string()]), exclude_buttons: vec!["button_9".to_string()], ..EventFilter::default() };
+    assert!(filter.allows(&event(EventType::ButtonPress(Button::Left))));
+    assert!(filter.allows(&event(EventType::ButtonRelease(Button::Unknown(8)))));
+    assert!(!filter.allows(&event(EventType::ButtonPress(Button::Unknown(9)))));
+    assert!(!filter.allows(&event(EventType::ButtonPress(Button::Right))));
+    assert!(filter.allows(&event(EventType::MouseMove { x: 1.0, y: 2.0 })), "moves are never filtered");
+    assert!(filter.allows(&event(EventType::Wheel { delta_x: 0, delta_y: 1 })));
+    assert!(filter.allows(&event(EventType::KeyPress(Key::KeyA))), "no key filter");
+}
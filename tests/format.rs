@@ -0,0 +1,89 @@
+use key_listener::clock::{EventTime, Timestamp};
+use key_listener::format::Format;
+use key_listener::models::{CommitReason, OutputEvent, Record, TextCommitEvent};
+use std::io::{Cursor, ErrorKind};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn record() -> Record {
+    let time = EventTime { timestamp: Timestamp(UNIX_EPOCH + Duration::from_millis(1_600_000_000_000)), monotonic_us: 1_250_000 };
+    let event: OutputEvent = TextCommitEvent { text: "héllo".to_string(), reason: CommitReason::Return, time }.into();
+    Record { seq: Some(7), ..Record::from(event) }
+}
+
+fn encode(format: Format, records: &[Record]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for record in records {
+        format.write(&mut bytes, record).unwrap();
+    }
+    bytes
+}
+
+#[test]
+fn binary_frames_round_trip() {
+    for format in [Format::MessagePack, Format::Cbor] {
+        let bytes = encode(format, &[record(), record()]);
+        let mut reader = Cursor::new(bytes);
+        for _ in 0..2 {
+            let frame = format.read_message(&mut reader).unwrap().expect("a frame");
+            let decoded: Record = format.decode(&frame).unwrap();
+            assert_eq!(serde_json::to_value(decoded).unwrap(), serde_json::to_value(record()).unwrap(), "{}", format);
+        }
+        assert!(format.read_message(&mut reader).unwrap().is_none(), "{} ends cleanly between frames", format);
+    }
+}
+
+#[test]
+fn frames_are_length_prefixed() {
+    let bytes = encode(Format::Cbor, &[record()]);
+    let len = u32::from_be_bytes(bytes[..4].try_into().unwrap()) as usize;
+    assert_eq!(len, bytes.len() - 4);
+}
+
+#[test]
+fn truncated_frames_are_errors() {
+    for format in [Format::MessagePack, Format::Cbor] {
+        let bytes = encode(format, &[record()]);
+        for cut in [2, bytes.len() - 1] {
+            let error = format.read_message(&mut Cursor::new(&bytes[..cut])).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::UnexpectedEof, "{} cut at {}", format, cut);
+        }
+    }
+}
+
+#[test]
+fn oversized_length_prefixes_are_refused() {
+    let mut bytes = (64u32 * 1024 * 1024).to_be_bytes().to_vec();
+    bytes.extend_from_slice(b"not that long");
+    let error = Format::MessagePack.read_message(&mut Cursor::new(bytes)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "simulation")]
+#[test]
+fn simulation_commands_are_read_in_binary_formats() {
+    use enigo::{Axis, Direction, Key};
+    use key_listener::backend::{Action, RecordingBackend};
+    use key_listener::simulation::simulate_input;
+    use serde_json::json;
+
+    for format in [Format::MessagePack, Format::Cbor] {
+        let mut bytes = Vec::new();
+        format.write(&mut bytes, &json!({"event_type": "key", "key": "Return", "action": "tap", "delay_after_ms": 40})).unwrap();
+        format.write(&mut bytes, &json!({"event_type": "mouse", "action": "scroll", "delta_y": -2})).unwrap();
+        format.write(&mut bytes, &json!({"event_type": "text", "text": "ok"})).unwrap();
+
+        let mut backend = RecordingBackend::new();
+        simulate_input(&mut backend, Cursor::new(bytes), format);
+        let actions: Vec<_> = backend.actions().iter().map(|recorded| (recorded.at.as_millis(), recorded.action.clone())).collect();
+        assert_eq!(
+            actions,
+            vec![
+                (0, Action::Key { key: Key::Return, direction: Direction::Click }),
+                (40, Action::Scroll { length: -2, axis: Axis::Vertical }),
+                (40, Action::Text("ok".to_string())),
+            ],
+            "{}",
+            format
+        );
+    }
+}
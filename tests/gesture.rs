@@ -0,0 +1,80 @@
+#![cfg(feature = "gesture")]
+
+mod common;
+
+use common::{press, release, replay, T0};
+use key_listener::gesture::{GestureHandler, GestureThresholds};
+use rdev::Key;
+use serde_json::json;
+
+// Defaults: taps up to 200 ms, 250 ms between taps, long presses from 500 ms
+fn handler() -> GestureHandler {
+    GestureHandler::new(GestureThresholds::default())
+}
+
+#[test]
+fn single_tap_is_reported_after_the_gap() {
+    let events = vec![press(0, Key::KeyA), release(100, Key::KeyA), press(1000, Key::KeyB)];
+    assert_eq!(
+        replay(handler(), events),
+        vec![json!({"event_type": "gesture", "key": "KeyA", "gesture": "tap", "count": 1, "duration_ms": 100, "timestamp": T0 + 350})]
+    );
+}
+
+#[test]
+fn double_tap_is_reported_when_another_key_is_pressed() {
+    let events = vec![press(0, Key::ShiftLeft), release(80, Key::ShiftLeft), press(200, Key::ShiftLeft), release(260, Key::ShiftLeft), press(300, Key::KeyA)];
+    assert_eq!(
+        replay(handler(), events),
+        vec![json!({"event_type": "gesture", "key": "ShiftLeft", "gesture": "double_tap", "count": 2, "duration_ms": 260, "timestamp": T0 + 300})]
+    );
+}
+
+#[test]
+fn double_tap_at_the_end_of_the_input_is_reported() {
+    let events = vec![press(0, Key::ShiftLeft), release(80, Key::ShiftLeft), press(200, Key::ShiftLeft), release(260, Key::ShiftLeft)];
+    let output = replay(handler(), events);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0]["gesture"], "double_tap");
+    assert_eq!(output[0]["duration_ms"], 260);
+}
+
+#[test]
+fn multi_tap_counts_every_tap() {
+    let mut events = Vec::new();
+    for tap in 0..4 {
+        events.push(press(tap * 200, Key::Space));
+        events.push(release(tap * 200 + 50, Key::Space));
+    }
+    events.push(press(2000, Key::KeyA));
+    let output = replay(handler(), events);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0]["gesture"], "multi_tap");
+    assert_eq!(output[0]["count"], 4);
+    assert_eq!(output[0]["duration_ms"], 650);
+}
+
+#[test]
+fn hold_is_reported_on_release() {
+    let events = vec![press(0, Key::KeyA), release(350, Key::KeyA)];
+    assert_eq!(
+        replay(handler(), events),
+        vec![json!({"event_type": "gesture", "key": "KeyA", "gesture": "hold", "count": 1, "duration_ms": 350, "timestamp": T0 + 350})]
+    );
+}
+
+#[test]
+fn long_press_is_reported_while_the_key_is_down() {
+    let events = vec![press(0, Key::Escape), press(600, Key::Escape), press(700, Key::KeyA), release(900, Key::Escape)];
+    assert_eq!(
+        replay(handler(), events),
+        vec![json!({"event_type": "gesture", "key": "Escape", "gesture": "long_press", "count": 1, "duration_ms": 500, "timestamp": T0 + 500})]
+    );
+}
+
+#[test]
+fn taps_of_one_key_come_before_its_long_press() {
+    let events = vec![press(0, Key::KeyA), release(50, Key::KeyA), press(150, Key::KeyA), release(1000, Key::KeyA)];
+    let gestures: Vec<_> = replay(handler(), events).iter().map(|event| event["gesture"].clone()).collect();
+    assert_eq!(gestures, vec![json!("tap"), json!("long_press")]);
+}
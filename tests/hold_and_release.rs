@@ -0,0 +1,42 @@
+#![cfg(feature = "hold_and_release")]
+
+mod common;
+
+use common::{press, release, replay, T0};
+use key_listener::hold_and_release::HoldAndReleaseHandler;
+use rdev::Key;
+use serde_json::json;
+
+#[test]
+fn sequence_ends_with_the_last_release() {
+    let events = vec![press(0, Key::ShiftLeft), press(100, Key::KeyA), press(200, Key::KeyA), release(250, Key::KeyA), release(300, Key::ShiftLeft)];
+    assert_eq!(
+        replay(HoldAndReleaseHandler::new(false), events),
+        vec![json!({
+            "event_type": "key_sequence",
+            "keys": ["ShiftLeft", "KeyA"],
+            "start_time": T0,
+            "end_time": T0 + 300,
+            "duration_ms": 300,
+            "duration_us": 300_000,
+        })]
+    );
+}
+
+
+#[test]
+fn timeline_follows_the_event_times() {
+    let events = vec![press(0, Key::KeyA), press(40, Key::KeyB), release(90, Key::KeyA), release(160, Key::KeyB), press(1000, Key::KeyC)];
+    let output = replay(HoldAndReleaseHandler::new(true), events);
+    assert_eq!(output.len(), 1, "the unfinished sequence is not reported");
+    assert_eq!(output[0]["duration_us"], 160_000);
+    assert_eq!(
+        output[0]["timeline"],
+        json!([
+            {"key": "KeyA", "pressed": true, "offset_us": 0},
+            {"key": "KeyB", "pressed": true, "offset_us": 40_000},
+            {"key": "KeyA", "pressed": false, "offset_us": 90_000, "hold_us": 90_000},
+            {"key": "KeyB", "pressed": false, "offset_us": 160_000, "hold_us": 120_000},
+        ])
+    );
+}
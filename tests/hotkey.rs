@@ -0,0 +1,105 @@
+#![cfg(feature = "hotkey")]
+
+mod common;
+
+use common::{press, release, replay, T0};
+use key_listener::hotkey::{parse_hotkeys, HotkeyHandler};
+use rdev::{Event, Key};
+use serde_json::{json, Value};
+
+fn handler(bindings: &[&str]) -> HotkeyHandler {
+    HotkeyHandler::new(parse_hotkeys(&bindings.iter().map(|binding| binding.to_string()).collect::<Vec<_>>()))
+}
+
+// The hotkey and phase of each record, without the timestamps
+fn phases(bindings: &[&str], events: Vec<Event>) -> Vec<(Value, Value)> {
+    replay(handler(bindings), events).into_iter().map(|event| (event["hotkey"].clone(), event["phase"].clone())).collect()
+}
+
+#[test]
+fn down_on_press_and_up_on_release() {
+    let events = vec![press(0, Key::ControlLeft), press(40, Key::KeyS), release(120, Key::KeyS), release(200, Key::ControlLeft)];
+    assert_eq!(
+        replay(handler(&["save = Ctrl+S"]), events),
+        vec![
+            json!({"event_type": "hotkey", "hotkey": "save", "phase": "down", "timestamp": T0 + 40}),
+            json!({"event_type": "hotkey", "hotkey": "save", "phase": "up", "timestamp": T0 + 120}),
+        ]
+    );
+}
+
+#[test]
+fn releasing_a_modifier_ends_the_hotkey() {
+    let events = vec![press(0, Key::ControlLeft), press(40, Key::KeyS), release(90, Key::ControlLeft), release(120, Key::KeyS)];
+    assert_eq!(
+        replay(handler(&["save = Ctrl+S"]), events),
+        vec![
+            json!({"event_type": "hotkey", "hotkey": "save", "phase": "down", "timestamp": T0 + 40}),
+            json!({"event_type": "hotkey", "hotkey": "save", "phase": "up", "timestamp": T0 + 90}),
+        ]
+    );
+}
+
+#[test]
+fn modifiers_must_match_exactly() {
+    let events = vec![press(0, Key::ControlLeft), press(10, Key::ShiftLeft), press(40, Key::KeyS), release(80, Key::KeyS)];
+    assert_eq!(phases(&["save = Ctrl+S"], events), vec![]);
+
+    let events = vec![press(0, Key::KeyS), release(80, Key::KeyS)];
+    assert_eq!(phases(&["save = Ctrl+S"], events), vec![]);
+
+    let events = vec![press(0, Key::ControlLeft), press(10, Key::KeyA), press(40, Key::KeyS)];
+    assert_eq!(phases(&["save = Ctrl+S"], events), vec![], "another key is held");
+}
+
+#[test]
+fn either_side_of_a_modifier_counts() {
+    let bindings = ["save = Ctrl+S", "launcher = Meta+Space"];
+    let events = vec![
+        press(0, Key::ControlRight), press(40, Key::KeyS), release(80, Key::KeyS), release(90, Key::ControlRight),
+        press(200, Key::MetaRight), press(240, Key::Space), release(280, Key::Space), release(290, Key::MetaRight),
+    ];
+    assert_eq!(
+        phases(&bindings, events),
+        vec![
+            (json!("save"), json!("down")),
+            (json!("save"), json!("up")),
+            (json!("launcher"), json!("down")),
+            (json!("launcher"), json!("up")),
+        ]
+    );
+}
+
+#[test]
+fn alt_takes_both_alt_keys_and_altgr_only_the_right_one() {
+    let bindings = ["alt = Alt+X", "altgr = AltGr+Y"];
+    let events = vec![
+        press(0, Key::Alt), press(10, Key::KeyX), release(20, Key::KeyX), press(30, Key::KeyY), release(40, Key::KeyY), release(50, Key::Alt),
+        press(100, Key::AltGr), press(110, Key::KeyX), release(120, Key::KeyX), press(130, Key::KeyY), release(140, Key::KeyY), release(150, Key::AltGr),
+    ];
+    assert_eq!(
+        phases(&bindings, events),
+        vec![
+            (json!("alt"), json!("down")),
+            (json!("alt"), json!("up")),
+            (json!("alt"), json!("down")),
+            (json!("alt"), json!("up")),
+            (json!("altgr"), json!("down")),
+            (json!("altgr"), json!("up")),
+        ]
+    );
+}
+
+#[test]
+fn auto_repeat_does_not_fire_again() {
+    let events = vec![
+        press(0, Key::ControlLeft), press(40, Key::KeyS), press(540, Key::KeyS), press(570, Key::KeyS), release(600, Key::KeyS),
+    ];
+    assert_eq!(phases(&["save = Ctrl+S"], events), vec![(json!("save"), json!("down")), (json!("save"), json!("up"))]);
+}
+
+#[test]
+fn hotkeys_still_held_go_up_when_the_input_ends() {
+    let events = vec![press(0, Key::ControlLeft), press(40, Key::KeyS)];
+    assert_eq!(phases(&["save = Ctrl+S"], events), vec![(json!("save"), json!("down")), (json!("save"), json!("up"))]);
+}
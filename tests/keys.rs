@@ -0,0 +1,49 @@
+use key_listener::keys::KeyName;
+
+#[test]
+fn every_name_parses_back_to_its_key() {
+    for key in KeyName::all().chain([KeyName::Unknown(250)]) {
+        let name = key.to_string();
+        assert_eq!(name.parse::<KeyName>(), Ok(key), "{}", name);
+        assert_eq!(name.to_lowercase().parse::<KeyName>(), Ok(key), "{}", name);
+    }
+}
+
+#[test]
+fn every_key_survives_serde() {
+    for key in KeyName::all().chain([KeyName::Unknown(250)]) {
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(json, format!("\"{}\"", key));
+        assert_eq!(serde_json::from_str::<KeyName>(&json).unwrap(), key);
+    }
+}
+
+#[test]
+fn every_key_the_hook_reports_maps_back_to_its_name() {
+    for key in KeyName::all() {
+        if let Some(rdev_key) = key.to_rdev() {
+            assert_eq!(KeyName::from(rdev_key), key);
+        }
+    }
+}
+
+#[test]
+fn characters_prefer_the_main_block() {
+    assert_eq!(KeyName::from_char('-'), Some(KeyName::Minus));
+    assert_eq!(KeyName::from_char('/'), Some(KeyName::Slash));
+    assert_eq!(KeyName::from_char('7'), Some(KeyName::Num7));
+    assert_eq!(KeyName::from_char('+'), Some(KeyName::KpPlus));
+    assert_eq!(KeyName::from_char('*'), Some(KeyName::KpMultiply));
+}
+
+#[cfg(feature = "simulation")]
+#[test]
+fn every_emitted_name_reaches_the_simulator() {
+    use key_listener::simulation::{simulated_key, string_to_key};
+
+    for key in KeyName::all() {
+        assert_eq!(string_to_key(&key.to_string()), simulated_key(key), "{}", key);
+    }
+    assert_eq!(string_to_key("KeyA"), Some(enigo::Key::Unicode('a')));
+    assert_eq!(string_to_key("Return"), Some(enigo::Key::Return));
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "direct")]
+
+mod common;
+
+use common::{press, release};
+use key_listener::listener::Listener;
+use key_listener::models::OutputEvent;
+use key_listener::source::{EventSource, ReplaySource, SourceError};
+use rdev::{Event, Key};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// A source like the OS hook, which never runs out of events
+struct Silent;
+
+impl EventSource for Silent {
+    fn listen(&mut self, _callback: Box<dyn FnMut(Event)>) -> Result<(), SourceError> {
+        loop {
+            thread::park();
+        }
+    }
+}
+
+fn collect() -> (Arc<Mutex<Vec<OutputEvent>>>, impl FnMut(OutputEvent) + Send + 'static) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let collected = Arc::clone(&events);
+    (events, move |event| collected.lock().unwrap().push(event))
+}
+
+#[test]
+fn delivers_replayed_events_to_the_callback() {
+    let (events, callback) = collect();
+    let source = ReplaySource::new(vec![press(0, Key::KeyA), release(80, Key::KeyA), press(200, Key::KeyB)]);
+    let handle = Listener::new().source(source).on_event(callback).start();
+    handle.join().unwrap();
+
+    let keys: Vec<_> = events
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|event| match event {
+            OutputEvent::Key(key) => (key.key.to_string(), key.pressed),
+            event => panic!("unexpected {:?}", event),
+        })
+        .collect();
+    assert_eq!(keys, vec![("KeyA".to_string(), true), ("KeyA".to_string(), false), ("KeyB".to_string(), true)]);
+}
+
+#[test]
+fn stop_ends_a_source_that_never_does() {
+    let (events, callback) = collect();
+    let handle = Listener::new().source(Silent).on_event(callback).start();
+    thread::sleep(Duration::from_millis(20));
+    assert!(!handle.is_finished());
+
+    let stopped_at = Instant::now();
+    handle.stop();
+    handle.join().unwrap();
+    assert!(stopped_at.elapsed() < Duration::from_secs(1));
+    assert!(events.lock().unwrap().is_empty());
+}
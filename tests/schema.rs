@@ -0,0 +1,21 @@
+use key_listener::schema::wire_schema;
+use std::{env, fs};
+
+const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/wire.schema.json");
+
+// Fails whenever a wire model changes without the checked-in schema following.
+// Run with UPDATE_SCHEMA=1 to rewrite the snapshot.
+#[test]
+fn schema_snapshot_is_up_to_date() {
+    let current = serde_json::to_string_pretty(&wire_schema()).unwrap() + "\n";
+    if env::var_os("UPDATE_SCHEMA").is_some() {
+        fs::write(SNAPSHOT, &current).unwrap();
+        return;
+    }
+
+    let snapshot = fs::read_to_string(SNAPSHOT).unwrap_or_default();
+    assert!(
+        snapshot == current,
+        "schema/wire.schema.json is out of date; regenerate it with `UPDATE_SCHEMA=1 cargo test --test schema`"
+    );
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "sequence")]
+
+mod common;
+
+use common::{press, release, replay, T0};
+use key_listener::sequence::{parse_sequences, SequenceHandler};
+use rdev::Key;
+use serde_json::json;
+use std::time::Duration;
+
+fn handler(bindings: &[&str]) -> SequenceHandler {
+    let bindings: Vec<String> = bindings.iter().map(|binding| binding.to_string()).collect();
+    SequenceHandler::new(parse_sequences(&bindings), Duration::from_millis(1000))
+}
+
+#[test]
+fn last_step_matches() {
+    let events = vec![
+        press(0, Key::ControlLeft),
+        press(50, Key::KeyK),
+        release(80, Key::KeyK),
+        press(300, Key::KeyC),
+        release(330, Key::KeyC),
+        release(400, Key::ControlLeft),
+    ];
+    assert_eq!(
+        replay(handler(&["comment = Ctrl+K Ctrl+C"]), events),
+        vec![json!({"event_type": "sequence", "sequence": "comment", "outcome": "match", "steps": ["Ctrl+KeyK", "Ctrl+KeyC"], "timestamp": T0 + 300})]
+    );
+}
+
+#[test]
+fn pause_times_out_at_the_deadline() {
+    let events = vec![press(0, Key::ControlLeft), press(50, Key::KeyK), release(80, Key::KeyK), release(100, Key::ControlLeft), press(2000, Key::KeyX)];
+    assert_eq!(
+        replay(handler(&["comment = Ctrl+K Ctrl+C"]), events),
+        vec![json!({"event_type": "sequence", "outcome": "timeout", "steps": ["Ctrl+KeyK"], "timestamp": T0 + 1050})]
+    );
+}
+
+#[test]
+fn prefix_binding_matches_on_timeout() {
+    let events = vec![press(0, Key::KeyG), release(50, Key::KeyG), press(2000, Key::KeyX)];
+    assert_eq!(
+        replay(handler(&["one = g", "top = g g"]), events),
+        vec![json!({"event_type": "sequence", "sequence": "one", "outcome": "match", "steps": ["KeyG"], "timestamp": T0 + 1000})]
+    );
+}
+
+#[test]
+fn prefix_binding_matches_when_the_input_ends() {
+    let output = replay(handler(&["one = g", "top = g g"]), vec![press(0, Key::KeyG), release(50, Key::KeyG)]);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0]["sequence"], "one");
+    assert_eq!(output[0]["outcome"], "match");
+}
+
+#[test]
+fn unfinished_sequence_times_out_when_the_input_ends() {
+    let output = replay(handler(&["top = g g"]), vec![press(0, Key::KeyG), release(50, Key::KeyG)]);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0]["outcome"], "timeout");
+    assert_eq!(output[0]["steps"], json!(["KeyG"]));
+}
+
+#[test]
+fn other_key_aborts_and_may_start_the_next_sequence() {
+    let events = vec![press(0, Key::KeyG), release(50, Key::KeyG), press(100, Key::KeyD), release(150, Key::KeyD), press(200, Key::KeyD)];
+    assert_eq!(
+        replay(handler(&["top = g g", "delete = d d"]), events),
+        vec![
+            json!({"event_type": "sequence", "outcome": "abort", "steps": ["KeyG", "KeyD"], "timestamp": T0 + 100}),
+            json!({"event_type": "sequence", "sequence": "delete", "outcome": "match", "steps": ["KeyD", "KeyD"], "timestamp": T0 + 200}),
+        ]
+    );
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "simulation")]
+
+use enigo::{Axis, Coordinate, Direction, Key};
+use key_listener::backend::{Action, RecordingBackend};
+use key_listener::simulation::simulate_event;
+use serde_json::json;
+use std::time::Duration;
+
+fn simulate(backend: &mut RecordingBackend, command: serde_json::Value) {
+    simulate_event(backend, serde_json::from_value(command).unwrap());
+}
+
+#[test]
+fn eased_moves_follow_the_curve() {
+    let mut backend = RecordingBackend::with_cursor(0, 0);
+    simulate(&mut backend, json!({"event_type": "mouse", "action": "move", "x": 100, "y": 40, "duration_ms": 20, "ease": "easeInQuad"}));
+    let moves: Vec<_> = backend
+        .actions()
+        .iter()
+        .map(|recorded| match recorded.action {
+            Action::MoveMouse { x, y, coordinate: Coordinate::Abs } => (recorded.at.as_millis(), x, y),
+            ref action => panic!("unexpected {:?}", action),
+        })
+        .collect();
+    // Every 5 ms, at (t / 20)² of the way, then exactly onto the target
+    assert_eq!(moves, vec![(0, 0, 0), (5, 6, 2), (10, 25, 10), (15, 56, 22), (20, 100, 40)]);
+}
+
+#[test]
+fn animated_scrolls_add_up_to_the_requested_amount() {
+    let mut backend = RecordingBackend::new();
+    simulate(&mut backend, json!({"event_type": "mouse", "action": "scroll", "delta_x": -3, "delta_y": 10, "duration_ms": 20, "ease": "linear"}));
+    let total = |axis: Axis| -> i32 {
+        backend
+            .actions()
+            .iter()
+            .filter_map(|recorded| match recorded.action {
+                Action::Scroll { length, axis: scrolled } if scrolled == axis => Some(length),
+                _ => None,
+            })
+            .sum()
+    };
+    assert_eq!(total(Axis::Horizontal), -3);
+    assert_eq!(total(Axis::Vertical), 10);
+}
+
+#[test]
+fn delays_advance_the_clock() {
+    let mut backend = RecordingBackend::new();
+    simulate(&mut backend, json!({"event_type": "key", "key": "Return", "action": "tap", "delay_after_ms": 100}));
+    simulate(&mut backend, json!({"event_type": "mouse", "action": "click", "button": "right", "delay_after_ms": 250}));
+    simulate(&mut backend, json!({"event_type": "key", "key": "Escape", "action": "press"}));
+    let times: Vec<_> = backend.actions().iter().map(|recorded| recorded.at).collect();
+    assert_eq!(times, vec![Duration::ZERO, Duration::from_millis(100), Duration::from_millis(350)]);
+    assert_eq!(backend.actions()[2].action, Action::Key { key: Key::Escape, direction: Direction::Press });
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "direct")]
+
+use key_listener::direct::DirectHandler;
+use key_listener::handler::run;
+use key_listener::sink::{MemorySink, SequencedSink};
+use key_listener::source::ReplaySource;
+use rdev::{Event, EventType, Key};
+use serde_json::json;
+use std::time::SystemTime;
+
+fn replay_into(sink: &SequencedSink, events: Vec<EventType>) {
+    let events = events.into_iter().map(|event_type| Event { time: SystemTime::now(), name: None, event_type }).collect();
+    run(&mut ReplaySource::new(events), DirectHandler::new(false), sink.clone()).unwrap();
+}
+
+#[test]
+fn summaries_are_numbered_but_not_counted() {
+    let memory = MemorySink::new();
+    let sink = SequencedSink::new(memory.clone());
+    replay_into(&sink, vec![EventType::KeyPress(Key::KeyA), EventType::KeyRelease(Key::KeyA)]);
+    sink.emit_summary();
+    replay_into(&sink, vec![EventType::KeyPress(Key::KeyB)]);
+    sink.finish();
+
+    let records: Vec<_> = memory.records().iter().map(|record| serde_json::to_value(record).unwrap()).collect();
+    let seqs: Vec<_> = records.iter().map(|record| record["seq"].clone()).collect();
+    assert_eq!(seqs, vec![json!(0), json!(1), json!(2), json!(3), json!(4)]);
+    assert_eq!(records[2], json!({"seq": 2, "event_type": "summary", "emitted": 2, "failed": 0, "final": false}));
+    assert_eq!(records[4], json!({"seq": 4, "event_type": "summary", "emitted": 3, "failed": 0, "final": true}));
+    assert_eq!(sink.emitted(), 3);
+}
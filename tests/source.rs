@@ -0,0 +1,47 @@
+#![cfg(feature = "direct")]
+
+mod common;
+
+use common::{replay_source, T0};
+use key_listener::direct::DirectHandler;
+use key_listener::source::{ReplaySource, SourceError};
+use serde_json::json;
+use std::fs;
+use std::io::Cursor;
+use std::process;
+
+// A recording as written by serializing `rdev::Event`s one per line, starting at `T0`
+const RECORDING: &str = r#"{"time":{"secs_since_epoch":1600000000,"nanos_since_epoch":0},"name":"a","event_type":{"KeyPress":"KeyA"}}
+
+{"time":{"secs_since_epoch":1600000000,"nanos_since_epoch":80000000},"name":null,"event_type":{"KeyRelease":"KeyA"}}
+{"time":{"secs_since_epoch":1600000000,"nanos_since_epoch":120000000},"name":null,"event_type":{"MouseMove":{"x":10.0,"y":20.0}}}
+"#;
+
+fn expected() -> Vec<serde_json::Value> {
+    vec![
+        json!({"event_type": "key", "key": "KeyA", "pressed": true, "text": "a", "modifiers": {}, "timestamp": T0}),
+        json!({"event_type": "key", "key": "KeyA", "pressed": false, "modifiers": {}, "timestamp": T0 + 80}),
+    ]
+}
+
+#[test]
+fn replays_a_jsonl_recording() {
+    let source = ReplaySource::from_reader(Cursor::new(RECORDING)).unwrap();
+    assert_eq!(replay_source(DirectHandler::new(false), source), expected());
+}
+
+#[test]
+fn replays_a_jsonl_file() {
+    let path = std::env::temp_dir().join(format!("key-listener-replay-{}.jsonl", process::id()));
+    fs::write(&path, RECORDING).unwrap();
+    let source = ReplaySource::from_jsonl(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(replay_source(DirectHandler::new(false), source.unwrap()), expected());
+}
+
+#[test]
+fn a_line_that_is_not_an_event_is_an_error() {
+    let result = ReplaySource::from_reader(Cursor::new("{\"time\":1}\n"));
+    assert!(matches!(result, Err(SourceError::Parse(_))));
+    assert!(matches!(ReplaySource::from_jsonl("/nonexistent/recording.jsonl"), Err(SourceError::Io(_))));
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "stats")]
+
+mod common;
+
+use common::{press, release, replay, T0};
+use key_listener::stats::StatsHandler;
+use rdev::Key;
+use std::time::Duration;
+
+#[test]
+fn rates_and_holds_follow_the_event_times() {
+    let events = vec![press(0, Key::KeyA), release(300, Key::KeyA), press(400, Key::KeyB), release(500, Key::KeyB)];
+    let output = replay(StatsHandler::new(None), events);
+    assert_eq!(output.len(), 1);
+    let stats = &output[0];
+    assert_eq!(stats["final"], true);
+    assert_eq!(stats["active_ms"], 400);
+    assert_eq!(stats["keys_per_minute"], 150.0);
+    assert_eq!(stats["keys"]["KeyA"]["avg_hold_ms"], 300.0);
+    assert_eq!(stats["keys"]["KeyB"]["avg_hold_ms"], 100.0);
+}
+
+#[test]
+fn every_interval_passed_in_a_replay_is_reported() {
+    let events = vec![press(0, Key::KeyA), release(100, Key::KeyA), press(3500, Key::KeyB)];
+    let output = replay(StatsHandler::new(Some(Duration::from_secs(1))), events);
+    let finals: Vec<_> = output.iter().map(|stats| stats["final"].as_bool().unwrap()).collect();
+    assert_eq!(finals, vec![false, false, false, true]);
+    // The first interval ends a second after the mode started, just before the first event
+    let first = output[0]["timestamp"].as_u64().unwrap();
+    assert!((T0 + 900..=T0 + 1000).contains(&first), "{}", first);
+    assert_eq!(output[1]["timestamp"].as_u64().unwrap(), first + 1000);
+    assert_eq!(output[2]["presses"], 1);
+}
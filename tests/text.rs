@@ -0,0 +1,65 @@
+#![cfg(feature = "text")]
+
+mod common;
+
+use common::{press, release, replay, T0};
+use key_listener::text::TextHandler;
+use rdev::{Event, Key};
+use serde_json::json;
+use std::time::Duration;
+
+// A press that typed `text`, as the OS hook reports it
+fn typed(ms: u64, key: Key, text: &str) -> Event {
+    Event { name: Some(text.to_string()), ..press(ms, key) }
+}
+
+fn handler() -> TextHandler {
+    TextHandler::new(Duration::from_millis(2000))
+}
+
+#[test]
+fn return_commits_the_line() {
+    let events = vec![typed(0, Key::KeyH, "h"), release(50, Key::KeyH), typed(100, Key::KeyI, "i"), press(200, Key::Return)];
+    assert_eq!(
+        replay(handler(), events),
+        vec![json!({"event_type": "text_commit", "text": "hi", "reason": "return", "timestamp": T0 + 200})]
+    );
+}
+
+#[test]
+fn edits_apply_before_the_commit() {
+    let events = vec![
+        typed(0, Key::KeyA, "a"),
+        typed(100, Key::KeyC, "c"),
+        press(200, Key::LeftArrow),
+        typed(300, Key::KeyB, "b"),
+        press(400, Key::End),
+        typed(500, Key::KeyX, "x"),
+        press(600, Key::Backspace),
+        press(700, Key::Tab),
+    ];
+    let output = replay(handler(), events);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0]["text"], "abc");
+    assert_eq!(output[0]["reason"], "tab");
+}
+
+#[test]
+fn idle_commit_is_stamped_when_typing_stopped() {
+    let events = vec![typed(0, Key::KeyH, "h"), typed(100, Key::KeyI, "i"), typed(5000, Key::KeyO, "o"), press(5100, Key::Return)];
+    assert_eq!(
+        replay(handler(), events),
+        vec![
+            json!({"event_type": "text_commit", "text": "hi", "reason": "idle", "timestamp": T0 + 2100}),
+            json!({"event_type": "text_commit", "text": "o", "reason": "return", "timestamp": T0 + 5100}),
+        ]
+    );
+}
+
+#[test]
+fn text_still_being_typed_is_committed_when_the_input_ends() {
+    let output = replay(handler(), vec![typed(0, Key::KeyH, "h"), typed(100, Key::KeyI, "i")]);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0]["text"], "hi");
+    assert_eq!(output[0]["reason"], "end");
+}